@@ -0,0 +1,181 @@
+use {
+    crate::{
+        render::{Cell, Frame},
+        theme::Theme,
+    },
+    anyhow::{Context as _, Result},
+    rand::Rng,
+    std::{
+        fs,
+        io::{self, Read as _},
+        path::Path,
+    },
+    unicode_width::UnicodeWidthChar,
+};
+
+/// Text the rain falls over, like the output of a command piped in, that drops wear away
+///
+/// Every hit either knocks a character down a row or fades it out a bit more until it is gone,
+/// so the screen slowly clears.
+#[derive(Debug, Clone)]
+pub struct Backdrop {
+    /// Rows of characters from the top of the screen, `None` where there is nothing (left)
+    rows: Vec<Vec<Option<Letter>>>,
+}
+#[derive(Debug, Clone, Copy)]
+struct Letter {
+    c: char,
+    /// How worn away the character is, it disappears at 1
+    wear: f32,
+}
+impl Backdrop {
+    /// Chance for a hit to knock a character down a row instead of wearing it away
+    const KNOCK_CHANCE: f64 = 0.2;
+    /// Wear caused by a hit from a drop of mass 1
+    const WEAR_PER_HIT: f32 = 0.25;
+    const TAB_WIDTH: usize = 8;
+
+    /// Reads the text from a file, or from stdin if the path is `-`
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = if path == Path::new("-") {
+            let mut text = String::new();
+            io::stdin()
+                .read_to_string(&mut text)
+                .context("failed to read text from stdin")?;
+            text
+        } else {
+            fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?
+        };
+        Ok(Self::parse(&text))
+    }
+    /// Lays out text the way a terminal would show it, dropping colors and other escape codes
+    pub fn parse(text: &str) -> Self {
+        let rows = text
+            .lines()
+            .map(|line| {
+                let mut row = Vec::new();
+                let mut chars = line.chars();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\x1b' => {
+                            // skip the whole sequence, e.g. `\x1b[1;31m`
+                            if chars.next() == Some('[') {
+                                for c in chars.by_ref() {
+                                    if ('@'..='~').contains(&c) {
+                                        break;
+                                    }
+                                }
+                            }
+                        }
+                        '\t' => {
+                            let stop = (row.len() / Self::TAB_WIDTH + 1) * Self::TAB_WIDTH;
+                            row.resize(stop, None);
+                        }
+                        ' ' => row.push(None),
+                        // control and zero-width characters take up no cells
+                        c => {
+                            if let Some(width @ 1..) = c.width() {
+                                row.push(Some(Letter { c, wear: 0.0 }));
+                                // the second cell of a wide character stays empty
+                                row.resize(row.len() + width - 1, None);
+                            }
+                        }
+                    }
+                }
+                row
+            })
+            .collect();
+        Self { rows }
+    }
+    /// Whether there is a character left in the given cell
+    pub fn occupied(&self, x: i64, y: i64) -> bool {
+        let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) else {
+            return false;
+        };
+        self.letter(x, y).is_some()
+    }
+    fn letter(&self, x: usize, y: usize) -> Option<Letter> {
+        self.rows
+            .get(y)
+            .and_then(|row| row.get(x))
+            .copied()
+            .flatten()
+    }
+    /// Knocks the character in the given cell down a row, or wears it away by a drop of `mass`
+    ///
+    /// Characters knocked off the bottom of a map `height` rows tall are gone.
+    pub fn hit(&mut self, rand: &mut impl Rng, x: usize, y: usize, mass: f32, height: usize) {
+        let Some(mut letter) = self.letter(x, y) else {
+            return;
+        };
+        self.rows[y][x] = None;
+        if rand.random_bool(Self::KNOCK_CHANCE) {
+            if y + 1 >= height {
+                return;
+            }
+            if self.letter(x, y + 1).is_none() {
+                self.place(x, y + 1, letter);
+                return;
+            }
+        }
+        letter.wear += Self::WEAR_PER_HIT * mass;
+        if letter.wear < 1.0 {
+            self.rows[y][x] = Some(letter);
+        }
+    }
+    fn place(&mut self, x: usize, y: usize, letter: Letter) {
+        if self.rows.len() <= y {
+            self.rows.resize_with(y + 1, Vec::new);
+        }
+        let row = &mut self.rows[y];
+        if row.len() <= x {
+            row.resize(x + 1, None);
+        }
+        row[x] = Some(letter);
+    }
+    /// Draws the characters left, fading into the theme's background as they wear away
+    pub fn render(&self, frame: &mut Frame, theme: Option<&Theme>) {
+        for (y, row) in self.rows.iter().enumerate() {
+            for (x, letter) in row.iter().enumerate() {
+                if let Some(Letter { c, wear }) = *letter {
+                    frame.set(x, y, Cell::new(c, theme.map(|t| t.text(wear))));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        rand::{rngs::StdRng, SeedableRng},
+    };
+
+    #[test]
+    fn parses_text_like_a_terminal_shows_it() {
+        let backdrop = Backdrop::parse("a\tb\n\x1b[1;31mred\x1b[0m ア!");
+        assert!(backdrop.occupied(0, 0) && backdrop.occupied(8, 0));
+        assert!(!backdrop.occupied(1, 0));
+        let row = |y: usize| {
+            backdrop.rows[y]
+                .iter()
+                .map(|l| l.map_or(' ', |l| l.c))
+                .collect::<String>()
+        };
+        assert_eq!(row(1), "red ア !");
+    }
+
+    #[test]
+    fn hits_wear_the_text_away() {
+        let mut backdrop = Backdrop::parse("xyz\nxyz");
+        let mut rand = StdRng::seed_from_u64(1);
+        for _ in 0..100 {
+            for (x, y) in [(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)] {
+                backdrop.hit(&mut rand, x, y, 1.0, 2);
+            }
+        }
+        assert!(backdrop.rows.iter().flatten().all(Option::is_none));
+    }
+}
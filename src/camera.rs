@@ -0,0 +1,70 @@
+/// Perspective camera looking into the rain, projecting world positions onto the screen
+///
+/// World `x` and `y` are in cells as seen at the focal depth. Farther away everything looks
+/// smaller and moves slower, converging towards the center of the screen.
+#[derive(Debug, Clone, Copy)]
+pub struct Camera {
+    center_x: f32,
+    center_y: f32,
+}
+impl Camera {
+    /// Closest depth that is drawn, anything in front of it has passed the camera
+    pub const NEAR: f32 = 0.75;
+    /// Farthest depth that is drawn, anything behind it is too far away to see
+    pub const FAR: f32 = 4.0;
+    /// Depth at which one world unit is one cell
+    const FOCAL: f32 = 1.5;
+
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            center_x: width as f32 / 2.0,
+            center_y: height as f32 / 2.0,
+        }
+    }
+    /// How much larger than at the focal depth things look at depth `z`
+    pub fn scale(z: f32) -> f32 {
+        Self::FOCAL / z
+    }
+    /// Whether depth `z` is between the near and far planes
+    pub fn in_range(z: f32) -> bool {
+        (Self::NEAR..=Self::FAR).contains(&z)
+    }
+    /// How close depth `z` is, from 0 at the far plane to 1 at the near plane
+    pub fn nearness(z: f32) -> f32 {
+        ((Self::FAR - z) / (Self::FAR - Self::NEAR)).clamp(0.0, 1.0)
+    }
+    /// Screen position of a point in the world
+    pub fn project(&self, x: f32, y: f32, z: f32) -> (f32, f32) {
+        let scale = Self::scale(z);
+        (
+            self.center_x + (x - self.center_x) * scale,
+            self.center_y + (y - self.center_y) * scale,
+        )
+    }
+    /// World position at depth `z` that shows up at the given screen position
+    pub fn unproject(&self, x: f32, y: f32, z: f32) -> (f32, f32) {
+        let scale = Self::scale(z);
+        (
+            self.center_x + (x - self.center_x) / scale,
+            self.center_y + (y - self.center_y) / scale,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn projection_converges_on_the_center_with_depth() {
+        let camera = Camera::new(80, 24);
+        let (near_x, _) = camera.project(0.0, 0.0, Camera::NEAR);
+        let (far_x, _) = camera.project(0.0, 0.0, Camera::FAR);
+        assert!(near_x < far_x && far_x < 40.0);
+        assert_eq!(camera.project(40.0, 12.0, Camera::FAR), (40.0, 12.0));
+
+        let (x, y) = camera.unproject(3.0, 20.0, 2.5);
+        let (sx, sy) = camera.project(x, y, 2.5);
+        assert!((sx - 3.0).abs() < 1e-4 && (sy - 20.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,186 @@
+use crossterm::style::Color;
+
+/// How many colors the terminal can show, colors are picked in 24-bit and mapped down to this
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+pub enum ColorMode {
+    #[value(name = "truecolor")]
+    #[serde(rename = "truecolor")]
+    TrueColor,
+    #[value(name = "256")]
+    #[serde(rename = "256")]
+    Ansi256,
+    #[value(name = "16")]
+    #[serde(rename = "16")]
+    Ansi16,
+    #[value(name = "none")]
+    #[serde(rename = "none")]
+    None,
+}
+impl ColorMode {
+    /// The standard 16 colors with their usual RGB values
+    const ANSI_16: &[(Color, (u8, u8, u8))] = &[
+        (Color::Black, (0, 0, 0)),
+        (Color::DarkRed, (128, 0, 0)),
+        (Color::DarkGreen, (0, 128, 0)),
+        (Color::DarkYellow, (128, 128, 0)),
+        (Color::DarkBlue, (0, 0, 128)),
+        (Color::DarkMagenta, (128, 0, 128)),
+        (Color::DarkCyan, (0, 128, 128)),
+        (Color::Grey, (192, 192, 192)),
+        (Color::DarkGrey, (128, 128, 128)),
+        (Color::Red, (255, 0, 0)),
+        (Color::Green, (0, 255, 0)),
+        (Color::Yellow, (255, 255, 0)),
+        (Color::Blue, (0, 0, 255)),
+        (Color::Magenta, (255, 0, 255)),
+        (Color::Cyan, (0, 255, 255)),
+        (Color::White, (255, 255, 255)),
+    ];
+    /// Channel values of the 6x6x6 color cube in the 256 color palette
+    const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+    /// Guesses what the terminal supports from `NO_COLOR`, `COLORTERM` and `TERM`
+    pub fn detect() -> Self {
+        Self::detect_from(|name| std::env::var(name).ok())
+    }
+    fn detect_from(var: impl Fn(&str) -> Option<String>) -> Self {
+        // https://no-color.org
+        if var("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+            return Self::None;
+        }
+        if var("COLORTERM").is_some_and(|v| v == "truecolor" || v == "24bit") {
+            return Self::TrueColor;
+        }
+        match var("TERM") {
+            Some(term) if term == "dumb" => Self::None,
+            Some(term) if term.contains("256color") => Self::Ansi256,
+            _ => Self::Ansi16,
+        }
+    }
+    /// The mode after this one, for cycling through them with a key
+    pub fn next(self) -> Self {
+        match self {
+            Self::TrueColor => Self::Ansi256,
+            Self::Ansi256 => Self::Ansi16,
+            Self::Ansi16 => Self::None,
+            Self::None => Self::TrueColor,
+        }
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::TrueColor => "truecolor",
+            Self::Ansi256 => "256",
+            Self::Ansi16 => "16",
+            Self::None => "none",
+        }
+    }
+    /// The closest color the terminal can show, `None` when colors are off
+    pub fn quantize(self, color: Color) -> Option<Color> {
+        let Color::Rgb { r, g, b } = color else {
+            return (self != Self::None).then_some(color);
+        };
+        match self {
+            Self::TrueColor => Some(color),
+            Self::Ansi256 => Some(Color::AnsiValue(nearest_256((r, g, b)))),
+            Self::Ansi16 => Self::ANSI_16
+                .iter()
+                .min_by_key(|(_, rgb)| distance(*rgb, (r, g, b)))
+                .map(|(color, _)| *color),
+            Self::None => None,
+        }
+    }
+}
+
+/// Index of the closest entry in the color cube or grayscale ramp of the 256 color palette
+/// Moves a color towards white by `amount` (0-1)
+pub fn brighten(color: Color, amount: f32) -> Color {
+    let Color::Rgb { r, g, b } = color else {
+        return color;
+    };
+    let lift = |c: u8| c + ((255 - c) as f32 * amount.clamp(0.0, 1.0)) as u8;
+    Color::Rgb {
+        r: lift(r),
+        g: lift(g),
+        b: lift(b),
+    }
+}
+
+fn nearest_256(rgb: (u8, u8, u8)) -> u8 {
+    let level = |c: u8| {
+        (0..ColorMode::CUBE_LEVELS.len())
+            .min_by_key(|&i| ColorMode::CUBE_LEVELS[i].abs_diff(c))
+            .unwrap_or(0)
+    };
+    let (r, g, b) = (level(rgb.0), level(rgb.1), level(rgb.2));
+    let cube = (
+        ColorMode::CUBE_LEVELS[r],
+        ColorMode::CUBE_LEVELS[g],
+        ColorMode::CUBE_LEVELS[b],
+    );
+    // the ramp runs from 8 to 238 in steps of 10
+    let avg = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let gray_step = (avg.saturating_sub(3) / 10).min(23) as u8;
+    let gray = 8 + gray_step * 10;
+
+    if distance((gray, gray, gray), rgb) < distance(cube, rgb) {
+        232 + gray_step
+    } else {
+        16 + 36 * r as u8 + 6 * g as u8 + b as u8
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_mode_from_environment() {
+        let detect = |vars: &[(&str, &str)]| {
+            ColorMode::detect_from(|name| {
+                vars.iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, v)| v.to_string())
+            })
+        };
+        assert_eq!(detect(&[]), ColorMode::Ansi16);
+        assert_eq!(detect(&[("TERM", "xterm-256color")]), ColorMode::Ansi256);
+        assert_eq!(
+            detect(&[("TERM", "screen-256color"), ("COLORTERM", "truecolor")]),
+            ColorMode::TrueColor
+        );
+        assert_eq!(detect(&[("TERM", "dumb")]), ColorMode::None);
+        assert_eq!(
+            detect(&[("COLORTERM", "24bit"), ("NO_COLOR", "1")]),
+            ColorMode::None
+        );
+        assert_eq!(detect(&[("NO_COLOR", "")]), ColorMode::Ansi16);
+    }
+
+    #[test]
+    fn quantizes_to_nearest_palette_entry() {
+        let rgb = |r, g, b| Color::Rgb { r, g, b };
+        let color = rgb(0, 127, 255);
+        assert_eq!(ColorMode::TrueColor.quantize(color), Some(color));
+        assert_eq!(ColorMode::None.quantize(color), None);
+        // cube entry (0, 2, 5)
+        assert_eq!(
+            ColorMode::Ansi256.quantize(color),
+            Some(Color::AnsiValue(16 + 2 * 6 + 5))
+        );
+        // grays land on the ramp instead of the coarser cube
+        assert_eq!(
+            ColorMode::Ansi256.quantize(rgb(50, 50, 50)),
+            Some(Color::AnsiValue(232 + 4))
+        );
+        assert_eq!(
+            ColorMode::Ansi16.quantize(rgb(230, 20, 10)),
+            Some(Color::Red)
+        );
+        assert_eq!(ColorMode::Ansi16.quantize(color), Some(Color::Blue));
+    }
+}
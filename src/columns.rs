@@ -0,0 +1,84 @@
+use {
+    crate::render::{Cell, Frame},
+    crossterm::style::Color,
+};
+
+/// Something piled up on the bottom of the map, like snow or water, as a height (in cells) per
+/// column
+pub struct Columns {
+    heights: Vec<f32>,
+    /// Tallest any column may get, in cells
+    max_height: f32,
+}
+impl Columns {
+    /// Partial blocks indexed by eighths of a cell
+    const PARTIAL_BLOCKS: &[char] = &[' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    pub fn new(width: usize, max_height: f32) -> Self {
+        Self {
+            heights: vec![0.0; width],
+            max_height,
+        }
+    }
+    /// Height of the given column, 0 outside of the map
+    pub fn height(&self, x: usize) -> f32 {
+        self.heights.get(x).copied().unwrap_or(0.0)
+    }
+    /// Piles `amount` onto the given column up to the maximum height, returning whether the
+    /// column is on the map
+    pub fn add(&mut self, x: usize, amount: f32) -> bool {
+        let Some(h) = self.heights.get_mut(x) else {
+            return false;
+        };
+        *h = (*h + amount).min(self.max_height);
+        true
+    }
+    /// Moves `share` of the height difference between neighbouring columns from the higher to
+    /// the lower one, wherever the difference is more than `threshold`
+    pub fn level(&mut self, share: f32, threshold: f32) {
+        for x in 1..self.heights.len() {
+            let diff = self.heights[x] - self.heights[x - 1];
+            if diff.abs() > threshold {
+                let moved = diff * share;
+                self.heights[x] -= moved;
+                self.heights[x - 1] += moved;
+            }
+        }
+    }
+    /// Lowers every column by `amount`, down to nothing
+    pub fn lower(&mut self, amount: f32) {
+        for h in &mut self.heights {
+            *h = (*h - amount).max(0.0);
+        }
+    }
+    /// Rescales the columns to the new width and trims them to the new maximum height
+    pub fn resize(&mut self, width: usize, max_height: f32) {
+        let old = std::mem::take(&mut self.heights);
+        self.max_height = max_height;
+        self.heights = (0..width)
+            .map(|x| {
+                old.get(x * old.len() / width)
+                    .copied()
+                    .unwrap_or(0.0)
+                    .min(max_height)
+            })
+            .collect();
+    }
+    /// Draws every column as full blocks topped by a partial one
+    pub fn render(&self, frame: &mut Frame, fg: Option<Color>, map_height: usize) {
+        for (x, h) in self.heights.iter().enumerate() {
+            let full = *h as usize;
+            for y in 0..full.min(map_height) {
+                frame.set(x, map_height - 1 - y, Cell::new('█', fg));
+            }
+            let eighths = ((h - full as f32) * 8.0) as usize;
+            if eighths > 0 && full < map_height {
+                frame.set(
+                    x,
+                    map_height - 1 - full,
+                    Cell::new(Self::PARTIAL_BLOCKS[eighths], fg),
+                );
+            }
+        }
+    }
+}
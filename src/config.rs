@@ -0,0 +1,353 @@
+use {
+    crate::{
+        backdrop::Backdrop,
+        color::ColorMode,
+        input::{Action, KeyBindings},
+        physics::Physics,
+        rain::{Mode, RainEntity, Weather},
+        scene::{Scene, ScenePosition},
+        theme::{load_themes, Theme, ThemeConfig},
+        Opts,
+    },
+    anyhow::{bail, Context as _, Result},
+    itertools::Itertools,
+    serde::Deserialize,
+    std::{
+        collections::{BTreeMap, HashMap},
+        fmt::Display,
+        fs,
+        io::ErrorKind,
+        ops::RangeInclusive,
+        path::{Path, PathBuf},
+    },
+    tracing::debug,
+    unicode_width::UnicodeWidthChar,
+};
+
+/// Options read from the config file, anything left out falls back to the defaults
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileConfig {
+    no_color: Option<bool>,
+    color_mode: Option<ColorMode>,
+    spawn_rate: Option<u8>,
+    update_rate: Option<u64>,
+    fps: Option<u32>,
+    mode: Option<Mode>,
+    weather: Option<Weather>,
+    wind: Option<f32>,
+    gustiness: Option<f32>,
+    gravity: Option<f32>,
+    drag: Option<f32>,
+    storm: Option<bool>,
+    lightning_frequency: Option<f64>,
+    lightning_intensity: Option<u8>,
+    glyphs: Option<String>,
+    snow_glyphs: Option<String>,
+    show_stats: Option<bool>,
+    scene: Option<PathBuf>,
+    scene_position: Option<String>,
+    theme: Option<String>,
+    /// Custom themes by name, added to the built-in ones
+    themes: BTreeMap<String, ThemeConfig>,
+    /// Keys for each action, replacing that action's default keys
+    keys: HashMap<Action, Vec<String>>,
+}
+impl FileConfig {
+    /// Reads the config file at `path`, or the default location if `None`
+    ///
+    /// A missing file is only an error when the path was given explicitly.
+    pub fn read(path: Option<&Path>) -> Result<Self> {
+        let (path, explicit) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound && !explicit => {
+                debug!("no config file at {}", path.display());
+                return Ok(Self::default());
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("failed to read {}", path.display()));
+            }
+        };
+        debug!("loading config from {}", path.display());
+        toml::from_str(&text).with_context(|| format!("invalid config file {}", path.display()))
+    }
+}
+
+/// `$XDG_CONFIG_HOME/cli-rain/config.toml`, falling back to `~/.config` when it isn't set
+fn default_path() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("cli-rain").join("config.toml"))
+}
+
+/// Fully resolved configuration, layered as defaults < config file < environment < command line
+#[derive(Debug)]
+pub struct Config {
+    pub color_mode: ColorMode,
+    pub spawn_rate: u8,
+    pub update_rate: u64,
+    pub fps: u32,
+    pub mode: Mode,
+    pub weather: Weather,
+    pub wind: f32,
+    pub gustiness: f32,
+    pub physics: Physics,
+    pub storm: bool,
+    pub lightning_frequency: f64,
+    pub lightning_intensity: u8,
+    /// `None` draws drops as streaks with glyphs following their slope
+    pub glyphs: Option<Vec<char>>,
+    pub snow_glyphs: Vec<char>,
+    pub show_stats: bool,
+    pub scene: Option<Scene>,
+    /// Text to rain over, only ever given on the command line
+    pub backdrop: Option<Backdrop>,
+    /// Built-in and custom themes, cycled through in this order
+    pub themes: Vec<Theme>,
+    /// Index of the selected theme in `themes`
+    pub theme: usize,
+    pub keys: KeyBindings,
+}
+impl Config {
+    /// Reads the config file and layers the command line and environment on top of it
+    pub fn load(opts: &Opts) -> Result<Self> {
+        Self::resolve(opts, FileConfig::read(opts.config.as_deref())?)
+    }
+    /// Layers the command line and environment (both already merged by clap) over the file
+    pub fn resolve(opts: &Opts, file: FileConfig) -> Result<Self> {
+        let mode = opts.mode.or(file.mode).unwrap_or(Mode::Rain);
+        let themes = load_themes(&file.themes)?;
+        let default_theme = match mode {
+            Mode::Rain => None,
+            Mode::Matrix => Some("matrix".to_string()),
+        };
+        let theme = match opts.theme.clone().or(file.theme.clone()).or(default_theme) {
+            Some(name) => match themes.iter().position(|t| t.name == name) {
+                Some(i) => i,
+                None => bail!(
+                    "unknown theme {name:?}, available: {}",
+                    themes.iter().map(|t| t.name.as_str()).join(", ")
+                ),
+            },
+            None => 0,
+        };
+        Ok(Self {
+            color_mode: color_mode(opts.color_mode, opts.no_color)
+                .or(color_mode(file.color_mode, file.no_color))
+                .flatten()
+                .unwrap_or_else(ColorMode::detect),
+            spawn_rate: in_range(
+                "spawn_rate",
+                opts.spawn_rate.or(file.spawn_rate).unwrap_or(3),
+                1..=100,
+            )?,
+            update_rate: in_range(
+                "update_rate",
+                opts.update_rate.or(file.update_rate).unwrap_or(50),
+                1..=2000,
+            )?,
+            fps: in_range("fps", opts.fps.or(file.fps).unwrap_or(30), 1..=240)?,
+            mode,
+            weather: opts.weather.or(file.weather).unwrap_or(Weather::Rain),
+            wind: finite("wind", opts.wind.or(file.wind).unwrap_or(0.0))?,
+            gustiness: finite(
+                "gustiness",
+                opts.gustiness.or(file.gustiness).unwrap_or(0.0),
+            )?,
+            physics: Physics {
+                gravity: in_range(
+                    "gravity",
+                    opts.gravity
+                        .or(file.gravity)
+                        .unwrap_or(Physics::DEFAULT_GRAVITY),
+                    0.01..=10.0,
+                )?,
+                drag: in_range(
+                    "drag",
+                    opts.drag.or(file.drag).unwrap_or(Physics::DEFAULT_DRAG),
+                    0.001..=10.0,
+                )?,
+            },
+            storm: opts.storm.or(file.storm).unwrap_or(false),
+            lightning_frequency: in_range(
+                "lightning_frequency",
+                opts.lightning_frequency
+                    .or(file.lightning_frequency)
+                    .unwrap_or(4.0),
+                0.0..=600.0,
+            )?,
+            lightning_intensity: in_range(
+                "lightning_intensity",
+                opts.lightning_intensity
+                    .or(file.lightning_intensity)
+                    .unwrap_or(60),
+                0..=100,
+            )?,
+            glyphs: glyph_set(opts.glyphs.as_ref().or(file.glyphs.as_ref()))?,
+            snow_glyphs: match opts.snow_glyphs.as_ref().or(file.snow_glyphs.as_ref()) {
+                Some(chars) => glyphs("snow_glyphs", chars)?,
+                None => RainEntity::FLAKE_CHARS.to_vec(),
+            },
+            show_stats: opts.show_stats.or(file.show_stats).unwrap_or(false),
+            scene: match opts.scene.as_ref().or(file.scene.as_ref()) {
+                Some(path) => {
+                    let position = match opts.scene_position {
+                        Some(position) => position,
+                        None => file
+                            .scene_position
+                            .as_deref()
+                            .map(ScenePosition::parse)
+                            .transpose()
+                            .context("invalid scene_position")?
+                            .unwrap_or(ScenePosition::Bottom),
+                    };
+                    Some(Scene::load(path, position)?)
+                }
+                None => None,
+            },
+            backdrop: opts.over.as_deref().map(Backdrop::load).transpose()?,
+            themes,
+            theme,
+            keys: KeyBindings::with_overrides(&file.keys)?,
+        })
+    }
+}
+
+fn in_range<T: PartialOrd + Display>(name: &str, value: T, range: RangeInclusive<T>) -> Result<T> {
+    if !range.contains(&value) {
+        bail!(
+            "{name} must be between {} and {}, got {value}",
+            range.start(),
+            range.end()
+        );
+    }
+    Ok(value)
+}
+
+fn finite(name: &str, value: f32) -> Result<f32> {
+    if !value.is_finite() {
+        bail!("{name} must be a finite number, got {value}");
+    }
+    Ok(value)
+}
+
+/// The color mode set in one layer, `Some(None)` when colors were enabled without picking a mode
+fn color_mode(mode: Option<ColorMode>, no_color: Option<bool>) -> Option<Option<ColorMode>> {
+    match (mode, no_color) {
+        (Some(mode), _) => Some(Some(mode)),
+        (None, Some(true)) => Some(Some(ColorMode::None)),
+        (None, Some(false)) => Some(None),
+        (None, None) => None,
+    }
+}
+
+/// A named set of raindrop glyphs, or `custom:` followed by the characters to use
+///
+/// `streak` (the default) doesn't have any glyphs, drops get theirs from their slope.
+fn glyph_set(value: Option<&String>) -> Result<Option<Vec<char>>> {
+    let set = match value.map(String::as_str) {
+        None | Some("streak") => return Ok(None),
+        Some("ascii") => RainEntity::ASCII_CHARS.to_vec(),
+        Some("unicode") => RainEntity::UNICODE_CHARS.to_vec(),
+        Some("katakana") => RainEntity::KATAKANA_CHARS.to_vec(),
+        Some(value) => match value.strip_prefix("custom:") {
+            Some(chars) => glyphs("glyphs", chars)?,
+            None => bail!(
+                "unknown glyph set {value:?}, expected streak, ascii, unicode, katakana or \
+                 custom:<characters>"
+            ),
+        },
+    };
+    Ok(Some(set))
+}
+
+fn glyphs(name: &str, chars: &str) -> Result<Vec<char>> {
+    if chars.is_empty() {
+        bail!("{name} must contain at least one character");
+    }
+    // control and combining characters would throw off the grid
+    if let Some(c) = chars.chars().find(|c| c.width().is_none_or(|w| w == 0)) {
+        bail!("{name} can't contain {c:?}, it has no width on screen");
+    }
+    Ok(chars.chars().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, clap::Parser};
+
+    fn resolve(args: &[&str], file: &str) -> Result<Config> {
+        let opts = Opts::parse_from(["cli-rain"].iter().chain(args));
+        Config::resolve(&opts, toml::from_str(file)?)
+    }
+
+    #[test]
+    fn command_line_overrides_file_overrides_defaults() {
+        let file = r#"
+            spawn_rate = 20
+            weather = "snow"
+            storm = true
+        "#;
+        let config = resolve(&["-r", "40", "--storm=false"], file).unwrap();
+        assert_eq!(config.spawn_rate, 40);
+        assert_eq!(config.weather, Weather::Snow);
+        assert!(!config.storm);
+        assert_eq!(config.update_rate, 50);
+
+        let file = "color_mode = \"256\"";
+        let config = resolve(&[], file).unwrap();
+        assert_eq!(config.color_mode, ColorMode::Ansi256);
+        let config = resolve(&["--no-color"], file).unwrap();
+        assert_eq!(config.color_mode, ColorMode::None);
+        let config = resolve(&["--no-color", "--color-mode", "16"], file).unwrap();
+        assert_eq!(config.color_mode, ColorMode::Ansi16);
+    }
+
+    #[test]
+    fn rejects_bad_file_values() {
+        assert!(resolve(&[], "spawn_rate = 0").is_err());
+        assert!(resolve(&[], "gravity = 0.0").is_err());
+        assert!(resolve(&[], "lightning_frequency = nan").is_err());
+        assert!(resolve(&[], "lightning_frequency = -1.0").is_err());
+        assert!(resolve(&["--wind", "inf"], "").is_err());
+        assert!(resolve(&["--gustiness", "nan"], "").is_err());
+        assert!(resolve(&[], "glyphs = \"\"").is_err());
+        assert!(resolve(&[], "glyphs = \"custom:\"").is_err());
+        assert!(resolve(&[], "glyphs = \"custom:a\\u0301\"").is_err());
+        assert!(resolve(&[], "glyphs = \"custom:|/\"").is_ok());
+        assert!(resolve(&[], "unknown = 1").is_err());
+        assert!(resolve(&[], "[keys]\nquit = [\"s\"]").is_err());
+        assert!(resolve(&[], "[keys]\nquit = [\"x\"]\ntoggle-pause = [\"p\"]").is_ok());
+        // uppercase letters are different keys from the lowercase defaults
+        assert!(resolve(&[], "[keys]\ntoggle-pause = [\"S\", \"SPACE\"]").is_ok());
+        assert!(resolve(&[], "theme = \"nope\"").is_err());
+        assert!(resolve(&[], "[themes.neon]\ndrops = [\"#00ff\"]").is_err());
+    }
+
+    #[test]
+    fn custom_themes_can_be_selected() {
+        let file = r##"
+            theme = "sunset"
+            [themes.neon]
+            drops = ["#000000", "#ff00ff"]
+            [themes.rain]
+            drops = ["#ffffff"]
+        "##;
+        let config = resolve(&[], file).unwrap();
+        assert_eq!(config.themes[config.theme].name, "sunset");
+        let config = resolve(&["--theme", "neon"], file).unwrap();
+        assert_eq!(config.themes[config.theme].name, "neon");
+        // replacing a built-in theme keeps its place
+        assert_eq!(config.themes[0].name, "rain");
+        assert_eq!(config.themes.len(), Theme::built_in().len() + 1);
+    }
+}
@@ -0,0 +1,125 @@
+use {
+    crate::{
+        columns::Columns,
+        render::{Cell, Frame},
+    },
+    crossterm::style::Color,
+};
+
+/// Water on the bottom of the map: puddles stored as a depth (in cells) per column, and the
+/// ripples drops leave where they land
+pub struct Ground {
+    depths: Columns,
+    ripples: Vec<Ripple>,
+}
+/// Ring spreading out from where a drop landed
+struct Ripple {
+    x: usize,
+    /// Ticks since the drop landed, which is also how far the ring spread
+    age: u8,
+}
+impl Ground {
+    /// How much water a drop of mass 1 adds to its column
+    const DROP_VOLUME: f32 = 0.1;
+    /// How much every column drains per tick, so puddles only grow in heavy rain
+    const DRAIN_RATE: f32 = 0.006;
+    /// Share of the depth difference that flows over to a neighbouring column per tick
+    const SPREAD: f32 = 0.25;
+    /// Fraction of the map height puddles may fill, but never more than `MAX_DEPTH`
+    const MAX_FILL: f32 = 0.1;
+    const MAX_DEPTH: f32 = 2.0;
+    /// Ticks a ripple spreads for before it is gone
+    const RIPPLE_LIFETIME: u8 = 4;
+
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            depths: Columns::new(width, Self::max_depth(height)),
+            ripples: Vec::new(),
+        }
+    }
+    fn max_depth(height: usize) -> f32 {
+        (height as f32 * Self::MAX_FILL).min(Self::MAX_DEPTH)
+    }
+    /// Adds the water of a drop of `mass` landing in the given column, starting a ripple there
+    pub fn land(&mut self, x: usize, mass: f32) {
+        if self.depths.add(x, Self::DROP_VOLUME * mass) {
+            self.ripples.push(Ripple { x, age: 0 });
+        }
+    }
+    /// Spreads the ripples, levels out the puddles and drains them
+    pub fn update(&mut self) {
+        self.ripples.retain_mut(|r| {
+            r.age += 1;
+            r.age < Self::RIPPLE_LIFETIME
+        });
+
+        self.depths.level(Self::SPREAD, 0.0);
+        self.depths.lower(Self::DRAIN_RATE);
+    }
+    /// Rescales the columns to the new width and drops ripples that are out of it
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.depths.resize(width, Self::max_depth(height));
+        self.ripples.retain(|r| r.x < width);
+    }
+    /// Draws the puddles with `water` and the ripples on their surface with `ripple`, which
+    /// gives the color of a ripple from 1 when it starts to 0 when it is gone
+    pub fn render(
+        &self,
+        frame: &mut Frame,
+        water: Option<Color>,
+        ripple: impl Fn(f32) -> Option<Color>,
+        map_height: usize,
+    ) {
+        self.depths.render(frame, water, map_height);
+        for r in &self.ripples {
+            // on the surface of the puddle, or the ground if there is none
+            let Some(y) = (map_height - 1).checked_sub(self.depths.height(r.x) as usize) else {
+                continue;
+            };
+            let fg = ripple(1.0 - r.age as f32 / Self::RIPPLE_LIFETIME as f32);
+            let radius = r.age as usize;
+            if radius == 0 {
+                frame.set(r.x, y, Cell::new('~', fg));
+                continue;
+            }
+            if let Some(left) = r.x.checked_sub(radius) {
+                frame.set(left, y, Cell::new('(', fg));
+            }
+            frame.set(r.x + radius, y, Cell::new(')', fg));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn total(ground: &Ground) -> f32 {
+        (0..20).map(|x| ground.depths.height(x)).sum()
+    }
+
+    #[test]
+    fn puddles_grow_in_heavy_rain_and_drain_in_light_rain() {
+        let mut heavy = Ground::new(20, 40);
+        let mut light = Ground::new(20, 40);
+        for tick in 0..200 {
+            for x in 0..20 {
+                heavy.land(x, 0.5);
+                if (tick + x) % 20 == 0 {
+                    light.land(x, 0.5);
+                }
+            }
+            heavy.update();
+            light.update();
+        }
+        assert!(total(&heavy) > 20.0);
+        assert!((0..20).all(|x| heavy.depths.height(x) <= Ground::max_depth(40)));
+        assert!(total(&light) < 1.0);
+
+        for _ in 0..1000 {
+            heavy.update();
+        }
+        assert_eq!(total(&heavy), 0.0);
+        assert!(heavy.ripples.is_empty());
+    }
+}
@@ -0,0 +1,143 @@
+use {
+    anyhow::{bail, Context as _, Result},
+    crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    serde::Deserialize,
+    std::collections::HashMap,
+};
+
+/// Something the user asked for with a key press
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Quit,
+    /// Stop the process like ctrl-z normally would, raw mode keeps the terminal from doing it
+    Suspend,
+    MoreRain,
+    LessRain,
+    Faster,
+    Slower,
+    WindLeft,
+    WindRight,
+    /// Switch to the next color mode, from truecolor down to none
+    CycleColor,
+    CycleTheme,
+    /// Switch between rain and snow, settled snow melts once it stopped snowing
+    ToggleWeather,
+    TogglePause,
+    /// Advance the simulation by a single update while paused
+    Step,
+    ToggleSlowMotion,
+}
+
+/// A key that can be bound to an action, written like `q`, `space`, `up` or `ctrl-c`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    code: KeyCode,
+    ctrl: bool,
+}
+impl Key {
+    pub fn parse(s: &str) -> Result<Self> {
+        let (ctrl, name) = match s.strip_prefix("ctrl-") {
+            Some(name) if !name.is_empty() => (true, name),
+            _ => (false, s),
+        };
+        let mut chars = name.chars();
+        let code = match (chars.next(), chars.next()) {
+            // single characters are bound as typed, `Q` is a different key from `q`, but
+            // terminals report letters pressed with ctrl in lowercase
+            (Some(c), None) if ctrl => KeyCode::Char(c.to_ascii_lowercase()),
+            (Some(c), None) => KeyCode::Char(c),
+            // named keys are case insensitive
+            _ => match name.to_lowercase().as_str() {
+                "esc" | "escape" => KeyCode::Esc,
+                "space" => KeyCode::Char(' '),
+                "enter" => KeyCode::Enter,
+                "tab" => KeyCode::Tab,
+                "backspace" => KeyCode::Backspace,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                _ => bail!("unknown key {s:?}"),
+            },
+        };
+        Ok(Self { code, ctrl })
+    }
+}
+impl From<KeyEvent> for Key {
+    fn from(key: KeyEvent) -> Self {
+        Self {
+            code: key.code,
+            ctrl: key.modifiers.contains(KeyModifiers::CONTROL),
+        }
+    }
+}
+
+/// Which key triggers which action
+#[derive(Debug, Clone)]
+pub struct KeyBindings(HashMap<Key, Action>);
+impl KeyBindings {
+    const DEFAULTS: &[(Action, &[&str])] = &[
+        // raw mode turns ctrl-c and ctrl-z into plain key presses instead of signals
+        (Action::Quit, &["q", "esc", "ctrl-c"]),
+        (Action::Suspend, &["ctrl-z"]),
+        (Action::MoreRain, &["+", "="]),
+        (Action::LessRain, &["-", "_"]),
+        (Action::Faster, &["up"]),
+        (Action::Slower, &["down"]),
+        (Action::WindLeft, &["left"]),
+        (Action::WindRight, &["right"]),
+        (Action::CycleColor, &["c"]),
+        (Action::CycleTheme, &["t"]),
+        (Action::ToggleWeather, &["w"]),
+        (Action::TogglePause, &["space"]),
+        (Action::Step, &["."]),
+        (Action::ToggleSlowMotion, &["s"]),
+    ];
+
+    /// The default bindings, with the keys of every action in `overrides` replaced
+    pub fn with_overrides(overrides: &HashMap<Action, Vec<String>>) -> Result<Self> {
+        let mut bindings = HashMap::new();
+        for (action, keys) in Self::DEFAULTS {
+            if overrides.contains_key(action) {
+                continue;
+            }
+            for key in *keys {
+                bindings.insert(Key::parse(key)?, *action);
+            }
+        }
+        for (action, keys) in overrides {
+            for name in keys {
+                let key =
+                    Key::parse(name).with_context(|| format!("invalid key for {action:?}"))?;
+                if let Some(other) = bindings.insert(key, *action) {
+                    bail!("key {name:?} is bound to both {other:?} and {action:?}");
+                }
+            }
+        }
+        Ok(Self(bindings))
+    }
+    /// Maps a key press to the action bound to it
+    pub fn action(&self, key: KeyEvent) -> Option<Action> {
+        // some terminals also report releases and repeats
+        if key.kind == KeyEventKind::Release {
+            return None;
+        }
+        self.0.get(&Key::from(key)).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_named_keys_ignore_case() {
+        assert_ne!(Key::parse("Q").unwrap(), Key::parse("q").unwrap());
+        assert_eq!(Key::parse("Q").unwrap().code, KeyCode::Char('Q'));
+        assert_eq!(Key::parse("ctrl-C").unwrap(), Key::parse("ctrl-c").unwrap());
+        assert_eq!(Key::parse("SPACE").unwrap(), Key::parse("space").unwrap());
+        assert_eq!(Key::parse("Up").unwrap().code, KeyCode::Up);
+        assert!(Key::parse("Upp").is_err());
+    }
+}
@@ -0,0 +1,166 @@
+use {
+    crate::{
+        color::brighten,
+        render::{Cell, Frame},
+    },
+    crossterm::style::Color,
+    rand::{prelude::*, rngs::StdRng},
+};
+
+/// One segment of a lightning bolt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoltCell {
+    pub x: usize,
+    pub y: usize,
+    pub c: char,
+}
+
+/// Chance for the main channel of a bolt to fork on each row
+const FORK_CHANCE: f64 = 0.12;
+/// Chance for a fork to split again on each row
+const SUB_FORK_CHANCE: f64 = 0.04;
+/// Upper bound on branches per bolt, keeps huge terminals from growing a tree
+const MAX_BRANCHES: usize = 12;
+
+/// Generates a branching lightning bolt for a `width` x `height` area
+///
+/// The main channel random-walks from a random column on the top row down to the bottom row,
+/// forks split off it and die out after a few rows. The same seed always gives the same bolt.
+pub fn generate_bolt(seed: u64, width: usize, height: usize) -> Vec<BoltCell> {
+    let mut rand = StdRng::seed_from_u64(seed);
+    let mut cells = Vec::new();
+    if width == 0 || height == 0 {
+        return cells;
+    }
+
+    // (x, y, rows left, is main channel)
+    let mut branches = vec![(rand.random_range(0..width) as i64, 0, height, true)];
+    let mut spawned = 1;
+    while let Some((mut x, mut y, mut len, main)) = branches.pop() {
+        while len > 0 && y < height {
+            let dx = if main {
+                // the main channel wanders less so it reaches the ground
+                [-1, 0, 0, 1][rand.random_range(0..4)]
+            } else {
+                [-1, -1, 0, 1, 1][rand.random_range(0..5)]
+            };
+            if (0..width as i64).contains(&x) {
+                let c = match dx {
+                    -1 => '/',
+                    1 => '\\',
+                    _ => '|',
+                };
+                cells.push(BoltCell {
+                    x: x as usize,
+                    y,
+                    c,
+                });
+            }
+
+            let fork_chance = if main { FORK_CHANCE } else { SUB_FORK_CHANCE };
+            if spawned < MAX_BRANCHES && rand.random_bool(fork_chance) {
+                let fork_len = rand.random_range(2..=(height / 3).max(2));
+                branches.push((x, y + 1, fork_len, false));
+                spawned += 1;
+            }
+
+            x += dx;
+            y += 1;
+            len -= 1;
+        }
+    }
+    cells
+}
+
+/// Thunderstorm state, occasionally striking a bolt and flashing the scene
+#[derive(Default)]
+pub struct Storm {
+    bolt: Vec<BoltCell>,
+    /// Ticks the current bolt stays visible for
+    bolt_ticks: u8,
+    /// How bright the scene currently is (0-1)
+    flash: f32,
+}
+impl Storm {
+    /// Ticks a bolt stays on screen
+    const BOLT_TICKS: u8 = 4;
+    /// Fraction of the flash that is left after each tick
+    const FLASH_DECAY: f32 = 0.6;
+    const BOLT_COLOR: Color = Color::Rgb {
+        r: 255,
+        g: 255,
+        b: 210,
+    };
+
+    /// Ages the current bolt and flash, striking a new bolt with the given chance
+    pub fn update(
+        &mut self,
+        rand: &mut impl Rng,
+        strike_chance: f64,
+        intensity: f32,
+        width: usize,
+        height: usize,
+    ) {
+        self.flash *= Self::FLASH_DECAY;
+        self.bolt_ticks = self.bolt_ticks.saturating_sub(1);
+        if self.bolt_ticks == 0 {
+            self.bolt.clear();
+        }
+
+        if self.bolt.is_empty() && rand.random_bool(strike_chance.clamp(0.0, 1.0)) {
+            self.bolt = generate_bolt(rand.random(), width, height);
+            self.bolt_ticks = Self::BOLT_TICKS;
+            self.flash = intensity;
+        }
+    }
+    /// Forgets the current bolt, its cells no longer line up after a resize
+    pub fn clear(&mut self) {
+        self.bolt.clear();
+        self.bolt_ticks = 0;
+    }
+    /// Brightens everything drawn so far and draws the bolt on top
+    pub fn render(&self, frame: &mut Frame, no_color: bool) {
+        if !no_color && self.flash > 0.01 {
+            for cell in frame.cells_mut() {
+                if let Some(fg) = &mut cell.fg {
+                    *fg = brighten(*fg, self.flash);
+                }
+            }
+        }
+        let fg = (!no_color).then_some(Self::BOLT_COLOR);
+        for cell in &self.bolt {
+            frame.set(cell.x, cell.y, Cell::new(cell.c, fg));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_the_same_bolt() {
+        assert_eq!(generate_bolt(5, 80, 24), generate_bolt(5, 80, 24));
+        assert_ne!(generate_bolt(5, 80, 24), generate_bolt(6, 80, 24));
+    }
+
+    #[test]
+    fn bolts_stay_inside_the_area() {
+        for seed in 0..200 {
+            let (width, height) = (1 + seed as usize % 40, 1 + seed as usize % 17);
+            let bolt = generate_bolt(seed, width, height);
+            assert!(bolt.iter().all(|c| c.x < width && c.y < height));
+        }
+        assert!(generate_bolt(1, 0, 10).is_empty());
+    }
+
+    #[test]
+    fn main_channel_starts_on_the_top_row() {
+        for seed in 0..200 {
+            let bolt = generate_bolt(seed, 80, 24);
+            // forks split off below the top row, so the only cell there is the main channel's
+            assert_eq!(bolt[0].y, 0);
+            assert_eq!(bolt.iter().filter(|c| c.y == 0).count(), 1);
+        }
+    }
+}
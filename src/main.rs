@@ -1,241 +1,319 @@
+mod backdrop;
+mod camera;
+mod color;
+mod columns;
+mod config;
+mod ground;
+mod input;
+mod lightning;
+mod matrix;
+mod physics;
+mod rain;
+mod render;
+mod scene;
+mod settings;
+mod signals;
+mod snow;
+mod terminal;
+mod theme;
+mod toast;
+mod wind;
+
 use {
     anyhow::{bail, Context as _, Result},
-    clap::Parser,
-    colored::Colorize,
-    crossterm::{
-        event::{poll, read, Event, KeyCode},
-        execute, terminal,
-    },
-    itertools::Itertools,
-    rand::prelude::*,
-    rayon::prelude::*,
+    clap::{parser::ValueSource, CommandFactory, FromArgMatches, Parser},
+    color::ColorMode,
+    config::Config,
+    crossterm::event::{poll, read, Event},
+    input::Action,
+    rain::{Mode, RainMap, Weather},
+    rand::Rng,
+    render::{Frame, Renderer},
+    scene::ScenePosition,
+    settings::Settings,
+    signals::Signal,
     std::{
-        io::{stdout, Write},
-        ops::RangeInclusive,
-        process::exit,
-        time::Duration,
+        fs,
+        io::stdout,
+        path::PathBuf,
+        process::ExitCode,
+        time::{Duration, Instant},
     },
+    terminal::TerminalGuard,
+    toast::Toast,
     tracing::debug,
     tracing_subscriber::EnvFilter,
 };
 
+/// Upper bound on simulation ticks run between two frames
+const MAX_TICKS_PER_FRAME: u32 = 5;
+/// How many times longer a tick takes in slow motion
+const SLOW_MOTION_FACTOR: u32 = 4;
+
+/// Every option can also be set with a `CLI_RAIN_*` environment variable or in the config file,
+/// command line flags take precedence over the environment, which takes precedence over the file
 #[derive(Parser)]
 struct Opts {
-    #[clap(long)]
-    /// Disables color (used to show depth, lighter is closer)
-    no_color: bool,
-    #[clap(short = 'r', long, default_value_t = 3, value_parser = clap::value_parser!(u8).range(1..=100))]
-    /// How likely a new raindrop is to spawn in each top column every update (1-100)
-    spawn_rate: u8,
-    #[clap(short, long, default_value_t = 50, value_parser = clap::value_parser!(u64).range(1..=2000))]
-    /// How frequently to update the screen (in milliseconds)
-    update_rate: u64,
+    #[clap(long, env = "CLI_RAIN_CONFIG")]
+    /// Config file to use instead of $XDG_CONFIG_HOME/cli-rain/config.toml
+    config: Option<PathBuf>,
+    #[clap(long, env = "CLI_RAIN_NO_COLOR", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    /// Disables color (used to show depth, lighter is closer), short for `--color-mode=none`
+    no_color: Option<bool>,
+    #[clap(long, env = "CLI_RAIN_COLOR_MODE", value_enum)]
+    /// Colors the terminal supports, detected from NO_COLOR, COLORTERM and TERM by default
+    color_mode: Option<ColorMode>,
+    #[clap(short = 'r', long, env = "CLI_RAIN_SPAWN_RATE", value_parser = clap::value_parser!(u8).range(1..=100))]
+    /// How likely a new raindrop is to spawn in each top column every update (1-100) [default: 3]
+    spawn_rate: Option<u8>,
+    #[clap(short, long, env = "CLI_RAIN_UPDATE_RATE", value_parser = clap::value_parser!(u64).range(1..=2000))]
+    /// How frequently to step the simulation (in milliseconds) [default: 50]
+    update_rate: Option<u64>,
+    #[clap(long, env = "CLI_RAIN_FPS", value_parser = clap::value_parser!(u32).range(1..=240))]
+    /// Maximum number of frames drawn per second [default: 30]
+    fps: Option<u32>,
+    #[clap(short, long, env = "CLI_RAIN_MODE", value_enum)]
+    /// What to simulate, matrix draws streams of glyphs and ignores the weather and wind
+    /// [default: rain]
+    mode: Option<Mode>,
+    #[clap(short, long, env = "CLI_RAIN_WEATHER", value_enum)]
+    /// What falls from the sky [default: rain]
+    weather: Option<Weather>,
+    #[clap(long, env = "CLI_RAIN_WIND", allow_negative_numbers = true)]
+    /// Steady horizontal wind in cells per update, negative blows to the left [default: 0]
+    wind: Option<f32>,
+    #[clap(long, env = "CLI_RAIN_GUSTINESS")]
+    /// Strength of random gusts on top of the steady wind, in cells per update [default: 0]
+    gustiness: Option<f32>,
+    #[clap(long, env = "CLI_RAIN_GRAVITY")]
+    /// How fast drops speed up, in cells per update² [default: 0.25]
+    gravity: Option<f32>,
+    #[clap(long, env = "CLI_RAIN_DRAG")]
+    /// Air resistance, raising it lowers the top speed of every drop [default: 0.028]
+    drag: Option<f32>,
+    #[clap(long, env = "CLI_RAIN_STORM", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    /// Adds a thunderstorm with lightning strikes
+    storm: Option<bool>,
+    #[clap(long, env = "CLI_RAIN_LIGHTNING_FREQUENCY")]
+    /// Average number of lightning strikes per minute during a storm (0-600) [default: 4]
+    lightning_frequency: Option<f64>,
+    #[clap(long, env = "CLI_RAIN_LIGHTNING_INTENSITY", value_parser = clap::value_parser!(u8).range(0..=100))]
+    /// How much a lightning strike brightens the scene (0-100) [default: 60]
+    lightning_intensity: Option<u8>,
+    #[clap(long, env = "CLI_RAIN_THEME")]
+    /// Color theme: rain, matrix, acid-rain, sunset, monochrome or one from the config file
+    /// [default: rain]
+    theme: Option<String>,
+    #[clap(long, env = "CLI_RAIN_GLYPHS")]
+    /// Characters raindrops are drawn with: streak, ascii, unicode, katakana or
+    /// custom:<characters>, streak picks them from the direction each drop moves [default: streak]
+    glyphs: Option<String>,
+    #[clap(long, env = "CLI_RAIN_SNOW_GLYPHS")]
+    /// Characters snowflakes are drawn with
+    snow_glyphs: Option<String>,
+    #[clap(long, env = "CLI_RAIN_SCENE")]
+    /// Text file with ASCII art drawn beneath the rain, drops hit everything but spaces
+    scene: Option<PathBuf>,
+    #[clap(long, env = "CLI_RAIN_SCENE_POSITION", value_parser = ScenePosition::parse)]
+    /// Where the scene is drawn: bottom (centered) or X,Y for its top left corner
+    /// [default: bottom]
+    scene_position: Option<ScenePosition>,
+    #[clap(long, env = "CLI_RAIN_OVER")]
+    /// Text to rain over until the drops wear it away, read from a file or from stdin with -
+    over: Option<PathBuf>,
+    #[clap(long, env = "CLI_RAIN_SEED")]
+    /// Seed for the simulation, the same seed and options give the same rain (random by default)
+    seed: Option<u64>,
+    #[clap(long, env = "CLI_RAIN_SHOW_STATS", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    /// Shows how many bytes were written to the terminal for the last frame
+    show_stats: Option<bool>,
+    #[clap(long, env = "CLI_RAIN_HEADLESS")]
+    /// Runs the simulation without a terminal and prints the final frame as plain text
+    headless: bool,
+    #[clap(long, env = "CLI_RAIN_FRAMES", default_value_t = 100)]
+    /// Number of updates to simulate in headless mode, ignored otherwise
+    frames: u32,
+    #[clap(long, env = "CLI_RAIN_SIZE", default_value = "80x24", value_parser = parse_size)]
+    /// Size of the map in headless mode as WIDTHxHEIGHT, ignored otherwise
+    size: (usize, usize),
+    #[clap(short, long, env = "CLI_RAIN_OUTPUT")]
+    /// Writes the final frame in headless mode to a file instead of stdout, ignored otherwise
+    output: Option<PathBuf>,
+}
+
+impl Opts {
+    /// Parses the command line and environment like `parse`, but lets options given on the
+    /// command line win over conflicting ones that only came from the environment
+    fn parse_layered() -> Self {
+        let matches = Self::command().get_matches();
+        let mut opts = Self::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+        let from_command_line =
+            |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        // `color_mode` wins over `no_color` when both are set, unless only the flag was typed
+        if from_command_line("no_color") && !from_command_line("color_mode") {
+            opts.color_mode = None;
+        }
+        opts
+    }
 }
 
-fn main() -> Result<()> {
+fn parse_size(s: &str) -> Result<(usize, usize)> {
+    let Some((width, height)) = s.split_once('x') else {
+        bail!("expected WIDTHxHEIGHT, e.g. 80x24");
+    };
+    let width = width.parse().context("invalid width")?;
+    let height = height.parse().context("invalid height")?;
+    if width == 0 || height == 0 {
+        bail!("width and height must be greater than 0");
+    }
+    Ok((width, height))
+}
+
+fn main() -> Result<ExitCode> {
     tracing_subscriber::fmt()
         .with_env_filter(EnvFilter::from_default_env())
         .init();
-    let opts = Opts::parse();
-
-    let mut stdout = stdout();
-    execute!(stdout, terminal::EnterAlternateScreen)?;
-    ctrlc::set_handler(handle_exit).context("failed to set ctrl-c handler")?;
+    let opts = Opts::parse_layered();
+    let config = Config::load(&opts)?;
 
-    let window_size = terminal::size().context("failed to get terminal window size")?;
-    debug!("window size: {}x{}", window_size.0, window_size.1);
+    let seed = opts.seed.unwrap_or_else(|| rand::rng().random());
+    debug!("seed: {seed}");
+    if opts.headless {
+        run_headless(&opts, &config, seed)?;
+        Ok(ExitCode::SUCCESS)
+    } else {
+        run(&config, seed)
+    }
+}
 
-    let mut rain_map = RainMap::new(window_size.0 as usize, window_size.1 as usize)?;
-    rain_map.hydrate(&opts);
+/// Simulates without touching the terminal and outputs the final frame
+fn run_headless(opts: &Opts, config: &Config, seed: u64) -> Result<()> {
+    let settings = Settings::from(config);
+    let (width, height) = opts.size;
+    let mut rain_map = RainMap::new(width, height, seed)?;
+    if let Some(backdrop) = &config.backdrop {
+        rain_map.set_backdrop(backdrop.clone());
+    }
+    rain_map.hydrate(&settings);
+    for _ in 0..opts.frames {
+        rain_map.update(&settings);
+        rain_map.hydrate(&settings);
+    }
 
-    loop {
-        if poll(Duration::from_millis(opts.update_rate))? {
-            match read()? {
-                Event::Resize(width, height) => rain_map.resize(width as usize, height as usize)?,
-                Event::Key(key) => {
-                    if key == KeyCode::Char('q').into() || key == KeyCode::Esc.into() {
-                        handle_exit();
-                    }
-                }
-                e => debug!("unhandled event: {e:?}"),
-            }
-        } else {
-            // no terminal events, loop as usual
-            write!(stdout, "{}", rain_map.render(&opts))?;
-            stdout.flush().context("failed to flush stdout")?;
-            rain_map.update();
-            rain_map.hydrate(&opts);
+    let mut frame = Frame::new(width, height);
+    rain_map.render(&settings, &mut frame, 0.0);
+    match &opts.output {
+        Some(path) => fs::write(path, frame.to_string())
+            .with_context(|| format!("failed to write frame to {}", path.display())),
+        None => {
+            print!("{frame}");
+            Ok(())
         }
     }
 }
 
-fn handle_exit() {
-    execute!(std::io::stdout(), terminal::LeaveAlternateScreen)
-        .expect("failed to exit alternate screen");
-    exit(0);
-}
+/// Draws the rain in the terminal until quit or ended by a signal, returning the exit status
+fn run(config: &Config, seed: u64) -> Result<ExitCode> {
+    let mut stdout = stdout();
+    let signals = signals::listen()?;
+    let _guard = TerminalGuard::enter()?;
 
-struct RainMap {
-    entities: Vec<(Pos, RainEntity)>,
-    height: usize,
-    width: usize,
-}
-impl RainMap {
-    pub fn new(width: usize, height: usize) -> Result<Self> {
-        if width == 0 || height == 0 {
-            bail!("width and height must be greater than 0");
-        }
-        Ok(Self {
-            entities: Vec::new(),
-            width,
-            height,
-        })
+    let window_size = crossterm::terminal::size().context("failed to get terminal window size")?;
+    debug!("window size: {}x{}", window_size.0, window_size.1);
+
+    let mut settings = Settings::from(config);
+    let mut toast = None::<Toast>;
+    let mut rain_map = RainMap::new(window_size.0 as usize, window_size.1 as usize, seed)?;
+    if let Some(backdrop) = &config.backdrop {
+        rain_map.set_backdrop(backdrop.clone());
     }
-    /// Adds new rain entities to the top of the map
-    pub fn hydrate(&mut self, opts: &Opts) {
-        let mut rand = rand::rng();
-        for x in 0..self.width {
-            let should_add = rand.random_bool(opts.spawn_rate as f64 / 100.0);
-            if should_add {
-                self.entities.push((
-                    Pos::new(x as i32, 0, rand.random_range(-16384..16384)),
-                    RainEntity::new(&mut rand),
-                ));
+    rain_map.hydrate(&settings);
+    let mut renderer = Renderer::new(rain_map.width(), rain_map.height());
+
+    let frame_time = Duration::from_secs(1) / config.fps;
+    let mut accumulator = Duration::ZERO;
+    let mut last_tick = Instant::now();
+    let mut next_frame = Instant::now();
+    loop {
+        while let Ok(signal) = signals.try_recv() {
+            debug!("received signal: {signal:?}");
+            match signal {
+                Signal::Exit(sig) => return Ok(signals::exit_code(sig)),
+                Signal::Suspend => suspend(&mut renderer)?,
+                Signal::Resume => {
+                    terminal::setup()?;
+                    renderer.redraw();
+                }
             }
         }
-    }
-    pub fn contains(&self, pos: &Pos) -> bool {
-        pos.x >= 0 && pos.x < self.width as i32 && pos.y >= 0 && pos.y < self.height as i32
-    }
-    pub fn resize(&mut self, width: usize, height: usize) -> Result<()> {
-        debug!(
-            "resizing from {}x{} to {}x{}",
-            self.width, self.height, width, height
-        );
-        if width == 0 || height == 0 {
-            bail!("width and height must be greater than 0");
-        }
-        self.width = width;
-        self.height = height;
-
-        let entities = self.entities.drain(..).collect_vec();
-        self.entities = entities
-            .into_par_iter()
-            .filter(|(p, _)| self.contains(p))
-            .collect();
-        Ok(())
-    }
-    /// Runs the rain simulation
-    pub fn update(&mut self) {
-        let entities = self.entities.drain(..).collect_vec();
-        self.entities = entities
-            .into_par_iter()
-            .filter_map(|(mut p, e)| {
-                p.shift(&e.velocity);
-                if self.contains(&p) {
-                    Some((p, e))
-                } else {
-                    None
+
+        // handle every pending event while waiting for the next frame
+        while poll(next_frame.saturating_duration_since(Instant::now()))? {
+            match read()? {
+                Event::Resize(width, height) => {
+                    rain_map.resize(width as usize, height as usize)?;
+                    renderer.resize(rain_map.width(), rain_map.height());
                 }
-            })
-            .collect();
-    }
-    pub fn render(&self, opts: &Opts) -> String {
-        let mut data = Vec::<Vec<Option<(i16, char)>>>::new();
-        for _ in 0..self.height {
-            let mut row = Vec::new();
-            for _ in 0..self.width {
-                row.push(None);
+                Event::Key(key) => match config.keys.action(key) {
+                    Some(Action::Quit) => return Ok(ExitCode::SUCCESS),
+                    Some(Action::Suspend) => suspend(&mut renderer)?,
+                    Some(Action::Step) => {
+                        if settings.paused {
+                            rain_map.update(&settings);
+                            rain_map.hydrate(&settings);
+                        }
+                    }
+                    Some(action) => toast = settings.apply(action).map(Toast::new),
+                    None => debug!("unbound key: {key:?}"),
+                },
+                e => debug!("unhandled event: {e:?}"),
             }
-            data.push(row);
         }
-        for (p, e) in self.entities.iter().filter(|(p, _)| self.contains(p)) {
-            let data_entry = &mut data[p.y as usize][p.x as usize];
-            let Some((z, c)) = data_entry else {
-                *data_entry = Some((p.z, e.c));
-                continue;
-            };
-            if *z > p.z {
-                // current entry is higher than canidate
-                continue;
-            }
-            *z = p.z;
-            *c = e.c;
+
+        let mut tick = Duration::from_millis(settings.update_rate);
+        if settings.slow_motion {
+            tick *= SLOW_MOTION_FACTOR;
         }
-        let mut output = String::new();
-        for row in data {
-            for col in row {
-                let Some((z, c)) = col else {
-                    output.push(' ');
-                    continue;
-                };
-                let s = if !opts.no_color {
-                    // normalize z (i16) to a u8
-                    let normalized_z = (((z as i32 - i16::MIN as i32) * 255)
-                        / (i16::MAX as i32 - i16::MIN as i32))
-                        as u8;
-
-                    format!("{c}")
-                        .truecolor(0, normalized_z.checked_div(2).unwrap_or(0), normalized_z)
-                        .to_string()
-                } else {
-                    format!("{c}")
-                };
-                output.push_str(&s);
-            }
-            output.push('\n');
+        let now = Instant::now();
+        if !settings.paused {
+            // don't try to catch up on more than a few ticks after a stall
+            accumulator = (accumulator + (now - last_tick)).min(tick * MAX_TICKS_PER_FRAME);
         }
-        output
-    }
-}
-
-#[derive(Debug, Clone, Copy)]
-struct RainEntity {
-    c: char,
-    velocity: Velocity,
-}
-impl RainEntity {
-    const AVAILABLE_CHARS: &[char] = &['\\', '/', '|', '~', '(', ')', '[', ']', '*', '#', '@'];
-    pub fn new(rand: &mut ThreadRng) -> Self {
-        Self {
-            c: Self::AVAILABLE_CHARS[rand.random_range(0..Self::AVAILABLE_CHARS.len())],
-            velocity: Velocity::new(rand),
+        last_tick = now;
+        while accumulator >= tick {
+            rain_map.update(&settings);
+            rain_map.hydrate(&settings);
+            accumulator -= tick;
         }
-    }
-}
 
-#[derive(Debug, Clone, Copy)]
-struct Velocity {
-    x: i32,
-    y: i32,
-    z: i16,
-}
-impl Velocity {
-    const X_RANGE: RangeInclusive<i32> = -3..=3;
-    const Y_RANGE: RangeInclusive<i32> = -3..=-1;
-    const Z_RANGE: RangeInclusive<i16> = -5248..=5248;
-    pub fn new(rand: &mut ThreadRng) -> Self {
-        Self {
-            x: rand.random_range(Self::X_RANGE),
-            y: rand.random_range(Self::Y_RANGE),
-            z: rand.random_range(Self::Z_RANGE),
+        let last_frame_bytes = renderer.last_frame_bytes();
+        renderer.set_color_mode(settings.color_mode);
+        let frame = renderer.begin_frame();
+        rain_map.render(
+            &settings,
+            frame,
+            accumulator.as_secs_f32() / tick.as_secs_f32(),
+        );
+        if config.show_stats {
+            let stats = format!(" {last_frame_bytes} bytes/frame, seed {seed} ");
+            frame.print(0, 0, &stats, None);
         }
+        toast = toast.filter(|t| !t.expired());
+        if let Some(toast) = &toast {
+            toast.render(frame);
+        }
+        renderer.present(&mut stdout)?;
+
+        next_frame = (next_frame + frame_time).max(now);
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-struct Pos {
-    x: i32,
-    y: i32,
-    z: i16,
-}
-impl Pos {
-    pub fn new(x: i32, y: i32, z: i16) -> Self {
-        Self { x, y, z }
-    }
-    pub fn shift(&mut self, vel: &Velocity) {
-        self.x += vel.x;
-        self.y -= vel.y;
-        self.z = self.z.saturating_add(vel.z);
-    }
+/// Hands the terminal back to the shell while the process is stopped, taking it over again once
+/// continued
+fn suspend(renderer: &mut Renderer) -> Result<()> {
+    terminal::restore();
+    signals::suspend()?;
+    terminal::setup()?;
+    renderer.redraw();
+    Ok(())
 }
@@ -0,0 +1,100 @@
+use {
+    crate::{
+        render::{Cell, Frame},
+        theme::Theme,
+    },
+    rand::Rng,
+    std::{collections::VecDeque, ops::RangeInclusive},
+};
+
+/// A column of glyphs falling down the screen in matrix mode, led by a bright head and followed
+/// by a fading trail
+///
+/// Glyphs stay in the row they were written in while the head moves on, and every now and then
+/// one of them changes into another.
+#[derive(Debug, Clone)]
+pub struct Stream {
+    x: usize,
+    /// Row of the head, with sub-cell precision
+    head: f32,
+    /// Rows moved per tick
+    speed: f32,
+    /// Glyphs from the head upwards, one per row
+    trail: VecDeque<char>,
+    /// Number of glyphs the trail grows to
+    length: usize,
+}
+impl Stream {
+    const SPEED_RANGE: RangeInclusive<f32> = 0.3..=1.2;
+    const LENGTH_RANGE: RangeInclusive<usize> = 6..=24;
+    /// Chance for each glyph in the trail to change every tick
+    const MUTATION_CHANCE: f64 = 0.04;
+    /// Share of digits among the glyphs, the rest is half-width katakana
+    const DIGIT_CHANCE: f64 = 0.2;
+
+    /// A stream entering the map from above in column `x`
+    pub fn new(rand: &mut impl Rng, x: usize) -> Self {
+        let speed = rand.random_range(Self::SPEED_RANGE);
+        Self {
+            x,
+            // spread spawns over the distance moved in one tick so streams don't start in rows
+            head: -rand.random::<f32>() * speed,
+            speed,
+            trail: VecDeque::new(),
+            length: rand.random_range(Self::LENGTH_RANGE),
+        }
+    }
+    pub fn x(&self) -> usize {
+        self.x
+    }
+    /// Whether the end of the trail has come down into the map, so a new stream can follow
+    pub fn entered(&self) -> bool {
+        self.head >= self.length as f32
+    }
+    /// Moves the head down and mutates the trail, returns false once the whole stream has left
+    /// the bottom of a map `height` rows high
+    pub fn update(&mut self, rand: &mut impl Rng, height: usize) -> bool {
+        for c in &mut self.trail {
+            if rand.random_bool(Self::MUTATION_CHANCE) {
+                *c = Self::random_glyph(rand);
+            }
+        }
+        let row = self.head.floor();
+        self.head += self.speed;
+        for _ in 0..(self.head.floor() - row) as usize {
+            self.trail.push_front(Self::random_glyph(rand));
+            self.trail.truncate(self.length);
+        }
+        self.head - (self.length as f32) < height as f32
+    }
+    /// Draws the stream, or uncolored when `theme` is `None`
+    ///
+    /// Streams move a whole row at a time, so unlike rain they aren't placed between ticks.
+    pub fn render(&self, frame: &mut Frame, theme: Option<&Theme>) {
+        let head = self.head.floor() as i64;
+        // faster streams look closer
+        let depth = (self.speed - Self::SPEED_RANGE.start())
+            / (Self::SPEED_RANGE.end() - Self::SPEED_RANGE.start());
+        for (i, c) in self.trail.iter().enumerate() {
+            let y = head - i as i64;
+            if y < 0 {
+                break;
+            }
+            let fg = theme.map(|theme| match i {
+                0 => theme.stream_head(),
+                _ => {
+                    let fade = 1.0 - i as f32 / self.length as f32;
+                    theme.stream(fade * (0.6 + 0.4 * depth))
+                }
+            });
+            frame.set(self.x, y as usize, Cell::new(*c, fg));
+        }
+    }
+    fn random_glyph(rand: &mut impl Rng) -> char {
+        if rand.random_bool(Self::DIGIT_CHANCE) {
+            rand.random_range('0'..='9')
+        } else {
+            rand.random_range('ｦ'..='ﾝ')
+        }
+    }
+}
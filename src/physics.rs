@@ -0,0 +1,100 @@
+/// Forces acting on falling drops, in cells and ticks
+///
+/// Drops are pulled down by gravity and slowed by air drag that grows with the square of their
+/// speed, so each one speeds up until it reaches a terminal velocity that depends on its mass.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Physics {
+    /// Downward acceleration, in cells per tick²
+    pub gravity: f32,
+    /// How strongly the air slows down a drop of mass 1
+    pub drag: f32,
+}
+impl Physics {
+    pub const DEFAULT_GRAVITY: f32 = 0.25;
+    pub const DEFAULT_DRAG: f32 = 0.028;
+
+    /// Falling speed one tick later for a drop of the given mass falling at `speed` (not rising)
+    ///
+    /// Uses the exact solution over the whole tick instead of a numerical step, so the speed
+    /// never overshoots the terminal velocity however strong the drag is.
+    pub fn fall(&self, speed: f32, mass: f32) -> f32 {
+        let terminal = self.terminal_velocity(mass);
+        let rate = (self.gravity * self.drag / mass).sqrt();
+        let ratio = speed.max(0.0) / terminal;
+        if ratio < 1.0 {
+            terminal * (ratio.atanh() + rate).tanh()
+        } else {
+            // slowing down from above the terminal velocity
+            terminal / ((1.0 / ratio).atanh() + rate).tanh()
+        }
+    }
+    /// Speed of a drop of the given mass after falling `distance` cells, starting at `speed`
+    pub fn speed_after(&self, speed: f32, distance: f32, mass: f32) -> f32 {
+        let terminal = self.terminal_velocity(mass).powi(2);
+        // the squared speed approaches the terminal one exponentially with the distance fallen
+        (terminal + (speed.powi(2) - terminal) * (-2.0 * self.drag / mass * distance).exp()).sqrt()
+    }
+    /// Speed at which drag cancels out gravity for a drop of the given mass
+    pub fn terminal_velocity(&self, mass: f32) -> f32 {
+        (self.gravity * mass / self.drag).sqrt()
+    }
+}
+impl Default for Physics {
+    fn default() -> Self {
+        Self {
+            gravity: Self::DEFAULT_GRAVITY,
+            drag: Self::DEFAULT_DRAG,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fall_for(physics: &Physics, mass: f32, ticks: usize) -> Vec<f32> {
+        let mut speed = 0.0;
+        (0..ticks)
+            .map(|_| {
+                speed = physics.fall(speed, mass);
+                speed
+            })
+            .collect()
+    }
+
+    #[test]
+    fn drops_accelerate_to_terminal_velocity() {
+        let physics = Physics::default();
+        let speeds = fall_for(&physics, 0.5, 200);
+        assert!(speeds.windows(2).all(|w| w[1] >= w[0]));
+        let terminal = physics.terminal_velocity(0.5);
+        assert!((speeds[199] - terminal).abs() < 1e-3);
+        // already at terminal velocity it stays there
+        assert!((physics.fall(terminal, 0.5) - terminal).abs() < 1e-5);
+        assert!((physics.speed_after(0.0, 1000.0, 0.5) - terminal).abs() < 1e-3);
+        assert!(physics.speed_after(0.0, 10.0, 0.5) < physics.speed_after(0.0, 20.0, 0.5));
+    }
+
+    #[test]
+    fn heavier_drops_fall_faster() {
+        let physics = Physics::default();
+        let mist = fall_for(&physics, 0.1, 20);
+        let drop = fall_for(&physics, 1.0, 20);
+        assert!(mist.iter().zip(&drop).skip(1).all(|(m, d)| d > m));
+        assert!(physics.terminal_velocity(1.0) > physics.terminal_velocity(0.1));
+    }
+
+    #[test]
+    fn strong_drag_never_overshoots() {
+        let physics = Physics {
+            gravity: 2.0,
+            drag: 1.0,
+        };
+        let terminal = physics.terminal_velocity(0.1);
+        assert!(fall_for(&physics, 0.1, 50)
+            .iter()
+            .all(|&s| s <= terminal + 1e-5));
+        // and fast drops slow down to it
+        assert!((physics.fall(5.0, 0.1) - terminal).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,813 @@
+use {
+    crate::{
+        backdrop::Backdrop,
+        camera::Camera,
+        color::ColorMode,
+        ground::Ground,
+        lightning::Storm,
+        matrix::Stream,
+        physics::Physics,
+        render::{Cell, Frame},
+        scene::Scene,
+        settings::Settings,
+        snow::SnowPack,
+        wind::Wind,
+    },
+    anyhow::{bail, Result},
+    itertools::Itertools,
+    rand::{prelude::*, rngs::StdRng},
+    rayon::{iter::Either, prelude::*},
+    std::{iter, ops::RangeInclusive},
+    tracing::debug,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Weather {
+    Rain,
+    Snow,
+}
+
+/// What the map simulates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// Rain or snow, depending on the weather
+    Rain,
+    /// Streams of glyphs falling straight down, like digital rain
+    Matrix,
+}
+
+/// What an entity left behind on the map this tick, applied once all of them moved
+enum Impact {
+    /// A flake settled on the snow in this column
+    Landed(usize),
+    /// A drop of this mass splashed onto the ground in this column
+    Splashed { x: usize, mass: f32 },
+    /// A drop hit a character of the backdrop in this cell
+    Hit { x: usize, y: usize, mass: f32 },
+}
+
+pub struct RainMap {
+    /// Entities with their positions in the world, seen through `camera`
+    entities: Vec<(Pos, RainEntity)>,
+    streams: Vec<Stream>,
+    snow: SnowPack,
+    /// Puddles and ripples, simulated on a grid alongside the entities
+    ground: Ground,
+    /// Text the rain wears away, if there is any
+    backdrop: Option<Backdrop>,
+    storm: Storm,
+    wind: Wind,
+    camera: Camera,
+    /// Source of all randomness in the simulation, so a seed reproduces a run
+    rng: StdRng,
+    height: usize,
+    width: usize,
+}
+impl RainMap {
+    pub fn new(width: usize, height: usize, seed: u64) -> Result<Self> {
+        if width == 0 || height == 0 {
+            bail!("width and height must be greater than 0");
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        Ok(Self {
+            entities: Vec::new(),
+            streams: Vec::new(),
+            snow: SnowPack::new(width, height),
+            ground: Ground::new(width, height),
+            backdrop: None,
+            storm: Storm::default(),
+            wind: Wind::new(rng.random()),
+            camera: Camera::new(width, height),
+            rng,
+            width,
+            height,
+        })
+    }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    /// Puts text behind the rain for it to wear away
+    pub fn set_backdrop(&mut self, backdrop: Backdrop) {
+        self.backdrop = Some(backdrop);
+    }
+    /// Adds new rain entities just outside the top and upwind edges of the screen
+    ///
+    /// Every column of the top edge spawns with the chance set by the spawn rate. A drop crosses
+    /// a row of the side edge `|vx| / |vy|` times as often as it crosses a column of the top edge,
+    /// so side spawns are scaled by that ratio to keep the density even across the whole map.
+    /// Perspective scales both speeds alike, so this holds at every depth.
+    pub fn hydrate(&mut self, settings: &Settings) {
+        if settings.mode == Mode::Matrix {
+            self.hydrate_streams(settings);
+            return;
+        }
+        let rand = &mut self.rng;
+        let chance = settings.spawn_rate as f64 / 100.0;
+        for x in 0..self.width {
+            if rand.random_bool(chance) {
+                let e = Self::new_entity(settings, rand);
+                let z = Self::new_z(rand);
+                let v = e.velocity_in(&self.wind, 0.0);
+                // spread spawns over the distance fallen in one tick so drops don't arrive in rows
+                let y = -rand.random::<f32>() * v.y.abs() * Camera::scale(z);
+                let (x, y) = self.camera.unproject(x as f32 + rand.random::<f32>(), y, z);
+                self.entities.push((Pos::new(x, y, z), e));
+            }
+        }
+        for y in 0..self.height {
+            let z = Self::new_z(rand);
+            // drops coming in from the side have been falling since they passed the top edge
+            let fallen = y as f32 / Camera::scale(z);
+            let e = Self::new_entity(settings, rand).after_falling(&settings.physics, fallen);
+            let v = e.velocity_in(&self.wind, y as f32);
+            let side_chance = chance * (v.x.abs() / v.y.abs()) as f64;
+            if v.x == 0.0 || !rand.random_bool(side_chance.min(1.0)) {
+                continue;
+            }
+            let offset = rand.random::<f32>() * v.x.abs() * Camera::scale(z);
+            let x = if v.x > 0.0 {
+                -offset
+            } else {
+                self.width as f32 + offset
+            };
+            let (x, y) = self.camera.unproject(x, y as f32 + rand.random::<f32>(), z);
+            self.entities.push((Pos::new(x, y, z), e));
+        }
+    }
+    /// Starts new streams with the chance set by the spawn rate in every column that doesn't
+    /// have a stream still coming in from above
+    fn hydrate_streams(&mut self, settings: &Settings) {
+        let mut busy = vec![false; self.width];
+        for stream in self.streams.iter().filter(|s| !s.entered()) {
+            if let Some(busy) = busy.get_mut(stream.x()) {
+                *busy = true;
+            }
+        }
+        let chance = settings.spawn_rate as f64 / 100.0;
+        for (x, busy) in busy.into_iter().enumerate() {
+            if !busy && self.rng.random_bool(chance) {
+                self.streams.push(Stream::new(&mut self.rng, x));
+            }
+        }
+    }
+    fn new_entity(settings: &Settings, rand: &mut impl Rng) -> RainEntity {
+        match settings.weather {
+            Weather::Rain => RainEntity::new(rand, settings.glyphs.as_deref(), &settings.physics),
+            Weather::Snow => RainEntity::new_flake(rand, &settings.snow_glyphs),
+        }
+    }
+    /// A random depth, log-uniform because far entities move slower and so stay on screen longer,
+    /// which evens out how many are seen at each depth
+    fn new_z(rand: &mut impl Rng) -> f32 {
+        Camera::NEAR * (Camera::FAR / Camera::NEAR).powf(rand.random())
+    }
+    /// Where a position in the world shows up on the screen
+    fn screen_pos(&self, pos: &Pos) -> (f32, f32) {
+        self.camera.project(pos.x, pos.y, pos.z)
+    }
+    fn on_screen(&self, (x, y): (f32, f32)) -> bool {
+        x >= 0.0 && x < self.width as f32 && y >= 0.0 && y < self.height as f32
+    }
+    pub fn resize(&mut self, width: usize, height: usize) -> Result<()> {
+        debug!(
+            "resizing from {}x{} to {}x{}",
+            self.width, self.height, width, height
+        );
+        if width == 0 || height == 0 {
+            bail!("width and height must be greater than 0");
+        }
+        self.width = width;
+        self.height = height;
+        self.camera = Camera::new(width, height);
+        self.snow.resize(width, height);
+        self.ground.resize(width, height);
+        self.storm.clear();
+        // entities outside of the new size are kept, so they show up again if the map grows back
+        // before the next update
+        Ok(())
+    }
+    /// Advances the rain simulation by one tick
+    pub fn update(&mut self, settings: &Settings) {
+        self.wind.update(settings.wind, settings.gustiness);
+        let entities = self.entities.drain(..).collect_vec();
+        // every entity gets its own rng derived from this, so the result doesn't depend on how
+        // the work is split between threads
+        let tick_seed: u64 = self.rng.random();
+        // entities that are still around on the left, what they left on the map on the right
+        let (entities, impacts): (Vec<_>, Vec<_>) = entities
+            .into_par_iter()
+            .enumerate()
+            .flat_map_iter(|(i, (mut p, mut e))| {
+                let before = self.screen_pos(&p);
+                // entities left below the map by shrinking it shouldn't splash
+                let was_above_bottom = before.1 < self.height as f32;
+                p.shift(&e.velocity_in(&self.wind, before.1));
+                // entities that drifted past the near or far plane disappear
+                if !e.tick(&settings.physics) || !Camera::in_range(p.z) {
+                    return vec![];
+                }
+                let (x, y) = self.screen_pos(&p);
+                let rand = || {
+                    StdRng::seed_from_u64(
+                        tick_seed ^ (i as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15),
+                    )
+                };
+                let hit = match (&self.backdrop, e.kind) {
+                    (Some(backdrop), EntityKind::Drop { mass }) => self
+                        .first_on_path(before, (x, y), |x, y| backdrop.occupied(x, y))
+                        .map(|(x, y)| {
+                            Either::Right(Impact::Hit {
+                                x: x as usize,
+                                y: y as usize,
+                                mass,
+                            })
+                        }),
+                    _ => None,
+                };
+                if let Some(scene) = &settings.scene {
+                    if let EntityKind::Runoff { .. } = e.kind {
+                        // soaks into whatever it runs into
+                        if self.solid(scene, (x, y)) {
+                            return vec![];
+                        }
+                        if !self.solid(scene, (x, y + 1.0)) {
+                            e = e.into_drip();
+                        }
+                    } else if let Some((hit_x, hit_y)) =
+                        self.first_on_path(before, (x, y), |x, y| {
+                            scene.solid(x, y, self.width, self.height)
+                        })
+                    {
+                        // flakes melt on the scene and splash particles just vanish
+                        let EntityKind::Drop { .. } = e.kind else {
+                            return vec![];
+                        };
+                        let mut rand = rand();
+                        let above = hit_y as f32 - 1.0;
+                        let mut out = self.splash(&mut rand, x, above, p.z);
+                        if rand.random_bool(RainEntity::RUNOFF_CHANCE) {
+                            let dir = match x - before.0 {
+                                dx if dx > 0.0 => 1.0,
+                                dx if dx < 0.0 => -1.0,
+                                _ if rand.random() => 1.0,
+                                _ => -1.0,
+                            };
+                            let speed = dir * RainEntity::RUNOFF_SPEED / Camera::scale(p.z);
+                            let (x, y) =
+                                self.camera.unproject(hit_x as f32 + 0.5, above + 0.5, p.z);
+                            out.push((Pos::new(x, y, p.z), e.into_runoff(speed)));
+                        }
+                        return out.into_iter().map(Either::Left).chain(hit).collect();
+                    }
+                }
+                let in_columns = x >= 0.0 && x < self.width as f32;
+                if matches!(e.kind, EntityKind::Flake { .. })
+                    && in_columns
+                    && y >= self.height as f32 - self.snow.height(x as usize)
+                {
+                    return vec![Either::Right(Impact::Landed(x as usize))];
+                }
+                if self.on_screen((x, y)) {
+                    return iter::once(Either::Left((p, e))).chain(hit).collect();
+                }
+                match e.kind {
+                    EntityKind::Drop { mass }
+                        if in_columns && was_above_bottom && y >= self.height as f32 =>
+                    {
+                        let landed = Impact::Splashed {
+                            x: x as usize,
+                            mass,
+                        };
+                        self.splash(&mut rand(), x, (self.height - 1) as f32, p.z)
+                            .into_iter()
+                            .map(Either::Left)
+                            .chain(hit)
+                            .chain(iter::once(Either::Right(landed)))
+                            .collect()
+                    }
+                    _ => hit.into_iter().collect(),
+                }
+            })
+            .partition_map(|e| e);
+        self.entities = entities;
+        // few enough that they aren't worth spreading over threads
+        let height = self.height;
+        let rand = &mut self.rng;
+        self.streams.retain_mut(|s| s.update(rand, height));
+        for impact in impacts {
+            match impact {
+                Impact::Landed(x) => self.snow.land(x),
+                Impact::Splashed { x, mass } => self.ground.land(x, mass),
+                Impact::Hit { x, y, mass } => {
+                    if let Some(backdrop) = &mut self.backdrop {
+                        backdrop.hit(&mut self.rng, x, y, mass, self.height);
+                    }
+                }
+            }
+        }
+        self.snow.update();
+        self.ground.update();
+
+        if settings.storm {
+            let strikes_per_tick =
+                settings.lightning_frequency / 60.0 * (settings.update_rate as f64 / 1000.0);
+            self.storm.update(
+                &mut self.rng,
+                strikes_per_tick,
+                settings.lightning_intensity as f32 / 100.0,
+                self.width,
+                self.height,
+            );
+        }
+    }
+    /// Splash particles thrown up from screen position `x`, `y` at depth `z`
+    fn splash(&self, rand: &mut impl Rng, x: f32, y: f32, z: f32) -> Vec<(Pos, RainEntity)> {
+        let (x, y) = self.camera.unproject(x, y, z);
+        let pos = Pos::new(x, y, z);
+        (0..rand.random_range(RainEntity::SPLASH_PARTICLES))
+            .map(|_| (pos, RainEntity::new_splash(rand)))
+            .collect()
+    }
+    /// Whether the screen cell at the given position is a solid part of the scene
+    fn solid(&self, scene: &Scene, (x, y): (f32, f32)) -> bool {
+        scene.solid(x.floor() as i64, y.floor() as i64, self.width, self.height)
+    }
+    /// First cell on the way between two screen positions that `hit` is true for, not counting
+    /// the cell the way starts in
+    fn first_on_path(
+        &self,
+        from: (f32, f32),
+        to: (f32, f32),
+        hit: impl Fn(i64, i64) -> bool,
+    ) -> Option<(i64, i64)> {
+        let cell = |(x, y): (f32, f32)| (x.floor() as i64, y.floor() as i64);
+        let start = cell(from);
+        let steps = (to.0 - from.0)
+            .abs()
+            .max((to.1 - from.1).abs())
+            .ceil()
+            .max(1.0) as usize;
+        (1..=steps)
+            .map(|k| {
+                let t = k as f32 / steps as f32;
+                (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t)
+            })
+            .map(cell)
+            .find(|&(x, y)| (x, y) != start && hit(x, y))
+    }
+    /// Draws the settled snow, the scene and the rain entities into the frame, closer entities
+    /// covering farther ones and looking brighter
+    ///
+    /// `alpha` is how far (0-1) the simulation is into the next tick, used to place entities
+    /// between their current and next positions.
+    pub fn render(&self, settings: &Settings, frame: &mut Frame, alpha: f32) {
+        let theme = settings.theme();
+        let no_color = settings.color_mode == ColorMode::None;
+        if !no_color {
+            frame.set_background(theme.background());
+        }
+        if let Some(backdrop) = &self.backdrop {
+            backdrop.render(frame, (!no_color).then_some(theme));
+        }
+        let snow_color = (!no_color).then(|| theme.snow());
+        self.snow.render(frame, snow_color, self.height);
+        self.ground.render(
+            frame,
+            (!no_color).then(|| theme.puddle()),
+            |t| (!no_color).then(|| theme.entity(&EntityKind::Splash, t)),
+            self.height,
+        );
+        if let Some(scene) = &settings.scene {
+            let scene_color = (!no_color).then(|| theme.scene());
+            scene.render(frame, scene_color, self.width, self.height);
+        }
+        for stream in &self.streams {
+            stream.render(frame, (!no_color).then_some(theme));
+        }
+        // z of the entity currently drawn in each cell
+        let mut depth = vec![None::<f32>; self.width * self.height];
+        for (p, e) in self.entities.iter() {
+            let v = e.velocity_in(&self.wind, self.screen_pos(p).1);
+            let head = self.screen_pos(&p.shifted(&v, alpha));
+            // drops are drawn along the path they covered in the last tick so fast ones don't
+            // skip rows, with the points on screen paired with how bright they are
+            let points = match e.kind {
+                EntityKind::Drop { .. } => {
+                    let tail = self.screen_pos(&p.shifted(&v, alpha - 1.0));
+                    let steps = (head.0 - tail.0)
+                        .abs()
+                        .max((head.1 - tail.1).abs())
+                        .ceil()
+                        .max(1.0) as usize;
+                    // from the tail to the head, so the head ends up on top of the streak
+                    (0..=steps)
+                        .rev()
+                        .map(|k| {
+                            let t = k as f32 / steps as f32;
+                            let point = (
+                                head.0 + (tail.0 - head.0) * t,
+                                head.1 + (tail.1 - head.1) * t,
+                            );
+                            (point, 1.0 - k as f32 / (steps + 1) as f32)
+                        })
+                        .collect_vec()
+                }
+                EntityKind::Splash | EntityKind::Flake { .. } | EntityKind::Runoff { .. } => {
+                    vec![(head, 1.0)]
+                }
+            };
+            let c = match (e.kind, &settings.glyphs) {
+                (EntityKind::Runoff { .. }, _) => RainEntity::RUNOFF_CHAR,
+                (EntityKind::Drop { .. }, None)
+                    if Camera::scale(p.z) < RainEntity::DISTANT_SCALE =>
+                {
+                    RainEntity::DISTANT_CHAR
+                }
+                (EntityKind::Drop { .. }, None) => v.slope_glyph(),
+                _ => e.c,
+            };
+            let nearness = Camera::nearness(p.z);
+            for (point, brightness) in points {
+                if !self.on_screen(point) {
+                    continue;
+                }
+                let (x, y) = (point.0 as usize, point.1 as usize);
+                let fg = (!no_color).then(|| theme.entity(&e.kind, nearness * brightness));
+                let cell = Cell::new(c, fg);
+                // double-width glyphs cover the cell to their right as well, and aren't drawn
+                // at all when that is off the frame
+                let covered = y * self.width + x..y * self.width + x + cell.width();
+                if x + cell.width() > self.width
+                    || depth[covered.clone()]
+                        .iter()
+                        .any(|z| z.is_some_and(|z| z < p.z))
+                {
+                    // current entry is closer than the candidate
+                    continue;
+                }
+                depth[covered].fill(Some(p.z));
+                frame.set(x, y, cell);
+            }
+        }
+        self.storm.render(frame, no_color);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntityKind {
+    /// Raindrop, heavier ones fall faster
+    Drop { mass: f32 },
+    /// Particle thrown up when a drop hits the bottom of the map
+    Splash,
+    /// Snowflake drifting side to side, `phase` is where it is in its sway
+    Flake { phase: f32 },
+    /// Water from a drop running along the top of the scene until it drips off an edge
+    Runoff { mass: f32 },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RainEntity {
+    c: char,
+    velocity: Velocity,
+    kind: EntityKind,
+    /// Remaining ticks before the entity disappears, `None` lives until it leaves the map
+    lifetime: Option<u8>,
+}
+impl RainEntity {
+    pub const ASCII_CHARS: &[char] = &['\\', '/', '|', '~', '(', ')', '[', ']', '*', '#', '@'];
+    pub const UNICODE_CHARS: &[char] = &['│', '┃', '╎', '╏', '┆', '┇', '┊', '┋', '╽', '╿', '¦'];
+    /// Full-width katakana, two cells wide each
+    pub const KATAKANA_CHARS: &[char] = &[
+        'ア', 'イ', 'ウ', 'エ', 'オ', 'カ', 'キ', 'ク', 'ケ', 'コ', 'サ', 'シ', 'ス', 'セ', 'ソ',
+        'タ', 'チ', 'ツ', 'テ', 'ト', 'ナ', 'ニ', 'ヌ', 'ネ', 'ノ', 'ハ', 'ヒ', 'フ', 'ヘ', 'ホ',
+        'マ', 'ミ', 'ム', 'メ', 'モ', 'ヤ', 'ユ', 'ヨ', 'ラ', 'リ', 'ル', 'レ', 'ロ', 'ワ', 'ン',
+    ];
+    const SPLASH_CHARS: &[char] = &['.', '\'', ','];
+    const SPLASH_PARTICLES: RangeInclusive<usize> = 2..=4;
+    const SPLASH_LIFETIME: RangeInclusive<u8> = 2..=4;
+    /// From fine mist to big drops, relative to the mass the drag is given for
+    const MASS_RANGE: RangeInclusive<f32> = 0.1..=1.0;
+    /// Share of its terminal velocity a drop starts out falling with
+    const SPAWN_SPEED: f32 = 0.3;
+    pub const FLAKE_CHARS: &[char] = &['*', '.', '❄'];
+    /// Chance for a drop hitting the scene to run off along it
+    const RUNOFF_CHANCE: f64 = 0.3;
+    /// Cells per tick runoff moves along the scene
+    const RUNOFF_SPEED: f32 = 0.5;
+    const RUNOFF_CHAR: char = '~';
+    /// Drops drawn as streaks look like specks beyond the depth where they are this small
+    const DISTANT_SCALE: f32 = 0.5;
+    const DISTANT_CHAR: char = '.';
+    const FLAKE_FALL_RANGE: RangeInclusive<f32> = -0.4..=-0.15;
+    /// Largest sideways movement of a flake in a single tick
+    const FLAKE_DRIFT: f32 = 0.35;
+    /// How far a flake advances through its sway for every cell it falls (radians)
+    const FLAKE_SWAY: f32 = 0.5;
+    /// A raindrop drawn with one of `glyphs`, or following its slope when there are none
+    ///
+    /// It starts out slower than its terminal velocity and speeds up as it falls.
+    pub fn new(rand: &mut impl Rng, glyphs: Option<&[char]>, physics: &Physics) -> Self {
+        let mass = rand.random_range(Self::MASS_RANGE);
+        Self {
+            c: glyphs.map_or('|', |glyphs| glyphs[rand.random_range(0..glyphs.len())]),
+            velocity: Velocity::new(rand, -physics.terminal_velocity(mass) * Self::SPAWN_SPEED),
+            kind: EntityKind::Drop { mass },
+            lifetime: None,
+        }
+    }
+    /// Water from this drop running along the scene, `speed` is in world units per tick
+    pub fn into_runoff(self, speed: f32) -> Self {
+        let EntityKind::Drop { mass } = self.kind else {
+            return self;
+        };
+        Self {
+            velocity: Velocity {
+                x: speed,
+                y: 0.0,
+                z: 0.0,
+            },
+            kind: EntityKind::Runoff { mass },
+            ..self
+        }
+    }
+    /// A drop falling from where this runoff ran out of scene to run along
+    pub fn into_drip(self) -> Self {
+        let EntityKind::Runoff { mass } = self.kind else {
+            return self;
+        };
+        Self {
+            velocity: Velocity {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            kind: EntityKind::Drop { mass },
+            ..self
+        }
+    }
+    /// The same entity, falling as fast as a drop does once it fell `distance` after spawning
+    pub fn after_falling(mut self, physics: &Physics, distance: f32) -> Self {
+        if let EntityKind::Drop { mass } = self.kind {
+            self.velocity.y = -physics.speed_after(-self.velocity.y, distance, mass);
+        }
+        self
+    }
+    pub fn new_splash(rand: &mut impl Rng) -> Self {
+        Self {
+            c: Self::SPLASH_CHARS[rand.random_range(0..Self::SPLASH_CHARS.len())],
+            velocity: Velocity {
+                x: rand.random_range(0.5..=1.5) * if rand.random() { 1.0 } else { -1.0 },
+                y: rand.random_range(0.5..=1.0),
+                z: 0.0,
+            },
+            kind: EntityKind::Splash,
+            lifetime: Some(rand.random_range(Self::SPLASH_LIFETIME)),
+        }
+    }
+    pub fn new_flake(rand: &mut impl Rng, glyphs: &[char]) -> Self {
+        let phase = rand.random_range(0.0..std::f32::consts::TAU);
+        Self {
+            c: glyphs[rand.random_range(0..glyphs.len())],
+            velocity: Velocity {
+                x: Self::FLAKE_DRIFT * phase.sin(),
+                y: rand.random_range(Self::FLAKE_FALL_RANGE),
+                z: rand.random_range(Velocity::Z_RANGE),
+            },
+            kind: EntityKind::Flake { phase },
+            lifetime: None,
+        }
+    }
+    /// How strongly the wind pushes this kind of entity around
+    fn wind_factor(&self) -> f32 {
+        match self.kind {
+            EntityKind::Drop { .. } => 1.0,
+            EntityKind::Splash => 0.5,
+            EntityKind::Flake { .. } => 1.5,
+            // sheltered by the scene it runs along
+            EntityKind::Runoff { .. } => 0.0,
+        }
+    }
+    /// Velocity of the entity after the wind at row `y` has been added
+    pub fn velocity_in(&self, wind: &Wind, y: f32) -> Velocity {
+        Velocity {
+            x: self.velocity.x + wind.at(y) * self.wind_factor(),
+            ..self.velocity
+        }
+    }
+    /// Ages the entity by one tick, returns false once it has expired
+    pub fn tick(&mut self, physics: &Physics) -> bool {
+        match &mut self.kind {
+            EntityKind::Drop { mass } => {
+                self.velocity.y = -physics.fall(-self.velocity.y, *mass);
+            }
+            // too short lived for drag to matter
+            EntityKind::Splash => self.velocity.y -= physics.gravity,
+            EntityKind::Flake { phase } => {
+                *phase += self.velocity.y.abs() * Self::FLAKE_SWAY;
+                self.velocity.x = Self::FLAKE_DRIFT * phase.sin();
+            }
+            EntityKind::Runoff { .. } => {}
+        }
+        match &mut self.lifetime {
+            Some(0) => false,
+            Some(l) => {
+                *l -= 1;
+                true
+            }
+            None => true,
+        }
+    }
+}
+
+/// Movement per simulation tick in world units, see [`Camera`]
+#[derive(Debug, Clone, Copy)]
+pub struct Velocity {
+    x: f32,
+    y: f32,
+    z: f32,
+}
+impl Velocity {
+    /// Small per drop variation, the wind decides which way the rain leans
+    const X_RANGE: RangeInclusive<f32> = -0.3..=0.3;
+    /// Slow enough that few drops cross the near or far plane before they land
+    const Z_RANGE: RangeInclusive<f32> = -0.002..=0.002;
+    /// Movement of a drop falling at `y`, with random sideways and depth drift
+    pub fn new(rand: &mut impl Rng, y: f32) -> Self {
+        Self {
+            x: rand.random_range(Self::X_RANGE),
+            y,
+            z: rand.random_range(Self::Z_RANGE),
+        }
+    }
+    /// Glyph leaning the way this moves across the screen
+    pub fn slope_glyph(&self) -> char {
+        // `y` points up, the screen's rows go down
+        let (dx, dy) = (self.x, -self.y);
+        if dx.abs() < dy.abs() / 2.0 {
+            '|'
+        } else if (dx > 0.0) == (dy > 0.0) {
+            '\\'
+        } else {
+            '/'
+        }
+    }
+}
+
+/// Position in the world, [`Camera`] projects it onto the screen
+#[derive(Debug, Clone, Copy)]
+pub struct Pos {
+    x: f32,
+    y: f32,
+    z: f32,
+}
+impl Pos {
+    pub fn new(x: f32, y: f32, z: f32) -> Self {
+        Self { x, y, z }
+    }
+    pub fn shift(&mut self, vel: &Velocity) {
+        *self = self.shifted(vel, 1.0);
+    }
+    /// Position after moving for `t` ticks
+    pub fn shifted(&self, vel: &Velocity, t: f32) -> Self {
+        Self {
+            x: self.x + vel.x * t,
+            y: self.y - vel.y * t,
+            z: self.z + vel.z * t,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            config::{Config, FileConfig},
+            input::Action,
+            Opts,
+        },
+        clap::Parser,
+    };
+
+    fn settings(args: &[&str]) -> Settings {
+        let opts = Opts::parse_from(["cli-rain"].iter().chain(args));
+        Settings::from(&Config::resolve(&opts, FileConfig::default()).unwrap())
+    }
+
+    /// Counts drops per column over many ticks once the map has filled up
+    fn column_coverage(args: &[&str], width: usize, height: usize) -> Vec<usize> {
+        let settings = settings(args);
+        let mut map = RainMap::new(width, height, 42).unwrap();
+        let mut counts = vec![0; width];
+        for tick in 0..2000 {
+            map.update(&settings);
+            map.hydrate(&settings);
+            if tick < 200 {
+                continue;
+            }
+            for (p, e) in &map.entities {
+                let (x, y) = map.screen_pos(p);
+                if matches!(e.kind, EntityKind::Drop { .. }) && map.on_screen((x, y)) {
+                    counts[x as usize] += 1;
+                }
+            }
+        }
+        counts
+    }
+
+    #[test]
+    fn same_seed_reproduces_the_run() {
+        let settings = settings(&["-r", "30", "--wind", "1", "--gustiness", "1"]);
+        let run = || {
+            let mut map = RainMap::new(80, 24, 7).unwrap();
+            for _ in 0..200 {
+                map.update(&settings);
+                map.hydrate(&settings);
+            }
+            format!("{:?}", map.entities)
+        };
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    fn diagonal_rain_covers_every_column() {
+        for wind in ["2", "-2"] {
+            let counts = column_coverage(&["-r", "20", "--wind", wind], 90, 30);
+            let mean = counts.iter().sum::<usize>() as f64 / counts.len() as f64;
+            for (x, &count) in counts.iter().enumerate() {
+                let ratio = count as f64 / mean;
+                assert!(
+                    (0.75..=1.25).contains(&ratio),
+                    "wind {wind}: column {x} has {ratio:.2} times the mean coverage"
+                );
+            }
+
+            let third = counts.len() / 3;
+            let left = counts[..third].iter().sum::<usize>() as f64;
+            let right = counts[counts.len() - third..].iter().sum::<usize>() as f64;
+            assert!(
+                (0.9..=1.1).contains(&(left / right)),
+                "wind {wind}: left third has {:.2} times the coverage of the right third",
+                left / right
+            );
+        }
+    }
+
+    #[test]
+    fn closer_wide_glyphs_cover_farther_ones() {
+        let settings = settings(&[]);
+        let mut map = RainMap::new(30, 10, 1).unwrap();
+        let glyph = |c| RainEntity {
+            c,
+            velocity: Velocity {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            kind: EntityKind::Splash,
+            lifetime: None,
+        };
+        let at = |x: f32, z: f32| {
+            let (x, y) = map.camera.unproject(x, 5.5, z);
+            Pos::new(x, y, z)
+        };
+        // farther glyphs overlapping either half of the closer one, drawn after it
+        map.entities = vec![
+            (at(10.5, 1.0), glyph('ア')),
+            (at(11.5, 3.0), glyph('イ')),
+            (at(9.5, 3.0), glyph('ウ')),
+        ];
+        let mut frame = Frame::new(30, 10);
+        map.render(&settings, &mut frame, 0.0);
+        let row = frame.to_string().lines().nth(5).unwrap().to_string();
+        assert_eq!(row.trim(), "ア");
+        assert_eq!(row.find('ア'), Some(10));
+    }
+
+    #[test]
+    fn snow_melts_once_the_weather_turns() {
+        let mut settings = settings(&["-w", "snow", "-r", "30"]);
+        let mut map = RainMap::new(40, 20, 3).unwrap();
+        let snow_depth = |map: &RainMap| (0..40).map(|x| map.snow.height(x)).sum::<f32>();
+        for _ in 0..500 {
+            map.update(&settings);
+            map.hydrate(&settings);
+        }
+        assert!(snow_depth(&map) > 1.0);
+
+        assert_eq!(
+            settings.apply(Action::ToggleWeather).as_deref(),
+            Some("weather: rain")
+        );
+        for _ in 0..2000 {
+            map.update(&settings);
+            map.hydrate(&settings);
+        }
+        assert_eq!(snow_depth(&map), 0.0);
+    }
+}
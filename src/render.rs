@@ -0,0 +1,301 @@
+use {
+    crate::color::ColorMode,
+    anyhow::{Context as _, Result},
+    crossterm::{
+        cursor::MoveTo,
+        queue,
+        style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor},
+        terminal::{Clear, ClearType},
+    },
+    std::{fmt, io::Write},
+    unicode_width::UnicodeWidthChar,
+};
+
+/// A single character cell on the screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub c: char,
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+}
+impl Cell {
+    pub const BLANK: Self = Self {
+        c: ' ',
+        fg: None,
+        bg: None,
+    };
+    /// Right half of a double-width character, covered by the cell to its left and never
+    /// printed itself
+    const CONTINUATION: Self = Self {
+        c: '\0',
+        fg: None,
+        bg: None,
+    };
+    pub fn new(c: char, fg: Option<Color>) -> Self {
+        Self { c, fg, bg: None }
+    }
+    /// Number of columns the character takes up on the screen, 1 or 2
+    pub fn width(&self) -> usize {
+        self.c.width().unwrap_or(1).clamp(1, 2)
+    }
+    fn is_continuation(&self) -> bool {
+        self.c == Self::CONTINUATION.c
+    }
+}
+
+/// A screen-sized grid of cells, stored row by row
+#[derive(Debug, Clone)]
+pub struct Frame {
+    cells: Vec<Cell>,
+    width: usize,
+    height: usize,
+}
+impl Frame {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            cells: vec![Cell::BLANK; width * height],
+            width,
+            height,
+        }
+    }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn clear(&mut self) {
+        self.cells.fill(Cell::BLANK);
+    }
+    pub fn cells_mut(&mut self) -> impl Iterator<Item = &mut Cell> {
+        self.cells.iter_mut()
+    }
+    /// Sets the background of every cell
+    pub fn set_background(&mut self, bg: Option<Color>) {
+        for cell in &mut self.cells {
+            cell.bg = bg;
+        }
+    }
+    /// Sets a cell, ignoring positions outside of the frame
+    ///
+    /// The cell keeps its current background unless the new one has its own. A double-width
+    /// character also covers the cell to its right and is left out if that is off the frame.
+    /// Characters partly covered by the new one are blanked so no half of them is left behind.
+    pub fn set(&mut self, x: usize, y: usize, cell: Cell) {
+        let wide = cell.width() == 2;
+        if x + wide as usize >= self.width || y >= self.height {
+            return;
+        }
+        let i = y * self.width + x;
+        self.break_wide(i);
+        if wide {
+            self.break_wide(i + 1);
+            self.cells[i + 1] = Cell {
+                bg: self.cells[i + 1].bg,
+                ..Cell::CONTINUATION
+            };
+        }
+        self.cells[i] = Cell {
+            bg: cell.bg.or(self.cells[i].bg),
+            ..cell
+        };
+    }
+    /// Blanks the other half of the double-width character at index `i`, if there is one
+    fn break_wide(&mut self, i: usize) {
+        let other = if self.cells[i].is_continuation() {
+            i - 1
+        } else if self.cells[i].width() == 2 {
+            i + 1
+        } else {
+            return;
+        };
+        self.cells[other] = Cell {
+            bg: self.cells[other].bg,
+            ..Cell::BLANK
+        };
+    }
+    /// Writes a line of text starting at the given position, clipped to the frame
+    pub fn print(&mut self, mut x: usize, y: usize, text: &str, fg: Option<Color>) {
+        for c in text.chars() {
+            let cell = Cell::new(c, fg);
+            self.set(x, y, cell);
+            x += cell.width();
+        }
+    }
+}
+
+/// Plain text of the frame without any colors, one line per row
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.cells.chunks(self.width) {
+            let line = row
+                .iter()
+                .filter(|cell| !cell.is_continuation())
+                .map(|cell| cell.c)
+                .collect::<String>();
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Double-buffered renderer that only writes the cells that changed since the last frame
+pub struct Renderer {
+    /// What is currently on the terminal
+    front: Frame,
+    /// The frame being drawn
+    back: Frame,
+    /// Set when the terminal contents are unknown (startup, resize)
+    full_redraw: bool,
+    buf: Vec<u8>,
+    last_frame_bytes: usize,
+    /// Colors in the frames are mapped to this when they are written
+    color_mode: ColorMode,
+}
+impl Renderer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            front: Frame::new(width, height),
+            back: Frame::new(width, height),
+            full_redraw: true,
+            buf: Vec::new(),
+            last_frame_bytes: 0,
+            color_mode: ColorMode::TrueColor,
+        }
+    }
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.front = Frame::new(width, height);
+        self.back = Frame::new(width, height);
+        self.full_redraw = true;
+    }
+    /// Redraws the whole screen on the next frame, for when the terminal contents are lost
+    pub fn redraw(&mut self) {
+        self.full_redraw = true;
+    }
+    /// Sets the colors the terminal supports, redrawing everything when it changes
+    pub fn set_color_mode(&mut self, mode: ColorMode) {
+        if mode != self.color_mode {
+            self.color_mode = mode;
+            self.full_redraw = true;
+        }
+    }
+    /// Clears the back buffer and returns it for drawing the next frame
+    pub fn begin_frame(&mut self) -> &mut Frame {
+        self.back.clear();
+        &mut self.back
+    }
+    /// Number of bytes written to the terminal for the last presented frame
+    pub fn last_frame_bytes(&self) -> usize {
+        self.last_frame_bytes
+    }
+    /// Writes the difference between the back buffer and what is on screen, then swaps buffers
+    pub fn present(&mut self, out: &mut impl Write) -> Result<()> {
+        self.buf.clear();
+        if self.full_redraw {
+            queue!(self.buf, ResetColor, Clear(ClearType::All))?;
+            self.front.clear();
+            self.full_redraw = false;
+        }
+
+        let mut cursor = None;
+        // the active colors are unknown at the start of every frame
+        let (mut fg, mut bg) = (None, None);
+        for y in 0..self.back.height {
+            for x in 0..self.back.width {
+                let i = y * self.back.width + x;
+                let cell = self.back.cells[i];
+                // the left half already printed the whole character
+                if cell == self.front.cells[i] || cell.is_continuation() {
+                    continue;
+                }
+                let cell = Cell {
+                    fg: cell.fg.and_then(|c| self.color_mode.quantize(c)),
+                    bg: cell.bg.and_then(|c| self.color_mode.quantize(c)),
+                    ..cell
+                };
+                if cursor != Some((x, y)) {
+                    queue!(self.buf, MoveTo(x as u16, y as u16))?;
+                }
+                // blank cells look the same in any foreground color
+                let fg_changed = cell.c != ' ' && fg != Some(cell.fg);
+                let bg_changed = bg != Some(cell.bg);
+                if (fg_changed && cell.fg.is_none()) || (bg_changed && cell.bg.is_none()) {
+                    // the only way back to the terminal's default colors
+                    queue!(self.buf, ResetColor)?;
+                    (fg, bg) = (Some(None), Some(None));
+                }
+                if cell.c != ' ' && fg != Some(cell.fg) {
+                    if let Some(color) = cell.fg {
+                        queue!(self.buf, SetForegroundColor(color))?;
+                    }
+                    fg = Some(cell.fg);
+                }
+                if bg != Some(cell.bg) {
+                    if let Some(color) = cell.bg {
+                        queue!(self.buf, SetBackgroundColor(color))?;
+                    }
+                    bg = Some(cell.bg);
+                }
+                queue!(self.buf, Print(cell.c))?;
+                cursor = Some((x + cell.width(), y));
+            }
+        }
+
+        out.write_all(&self.buf)
+            .context("failed to write frame to stdout")?;
+        out.flush().context("failed to flush stdout")?;
+        self.last_frame_bytes = self.buf.len();
+        std::mem::swap(&mut self.front, &mut self.back);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Draws a frame with `draw` and returns what presenting it wrote
+    fn present(renderer: &mut Renderer, draw: impl FnOnce(&mut Frame)) -> Vec<u8> {
+        draw(renderer.begin_frame());
+        let mut out = Vec::new();
+        renderer.present(&mut out).unwrap();
+        assert_eq!(renderer.last_frame_bytes(), out.len());
+        out
+    }
+
+    #[test]
+    fn only_changed_cells_are_written() {
+        let mut renderer = Renderer::new(10, 3);
+        let draw = |frame: &mut Frame| frame.set(2, 1, Cell::new('a', None));
+        assert!(!present(&mut renderer, draw).is_empty());
+        assert!(present(&mut renderer, draw).is_empty());
+
+        let out = present(&mut renderer, |frame| {
+            draw(frame);
+            frame.set(5, 2, Cell::new('b', None));
+        });
+        let mut expected = Vec::new();
+        queue!(expected, MoveTo(5, 2), ResetColor, Print('b')).unwrap();
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn wide_characters_advance_the_cursor_by_two() {
+        let mut renderer = Renderer::new(10, 1);
+        present(&mut renderer, |_| {});
+        let out = present(&mut renderer, |frame| {
+            frame.set(2, 0, Cell::new('ア', None));
+            frame.set(4, 0, Cell::new('b', None));
+        });
+        // the continuation cell isn't printed and the next character needs no cursor move
+        let mut expected = Vec::new();
+        queue!(expected, MoveTo(2, 0), ResetColor, Print('ア'), Print('b')).unwrap();
+        assert_eq!(out, expected);
+
+        // a narrow character replacing it has to clear the cell the right half was on
+        let out = present(&mut renderer, |frame| {
+            frame.set(2, 0, Cell::new('x', None));
+            frame.set(4, 0, Cell::new('b', None));
+        });
+        let mut expected = Vec::new();
+        queue!(expected, MoveTo(2, 0), ResetColor, Print('x'), Print(' ')).unwrap();
+        assert_eq!(out, expected);
+    }
+}
@@ -0,0 +1,98 @@
+use {
+    crate::render::{Cell, Frame},
+    anyhow::{bail, Context as _, Result},
+    crossterm::style::Color,
+    std::{fs, path::Path},
+    unicode_width::UnicodeWidthChar,
+};
+
+/// Where the scene is drawn on the screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScenePosition {
+    /// Standing on the bottom edge, centered
+    Bottom,
+    /// With its top left corner at the given column and row
+    At(usize, usize),
+}
+impl ScenePosition {
+    /// Parses `bottom` or a position written as `X,Y`
+    pub fn parse(s: &str) -> Result<Self> {
+        if s == "bottom" {
+            return Ok(Self::Bottom);
+        }
+        let Some((x, y)) = s.split_once(',') else {
+            bail!("expected bottom or X,Y, e.g. 10,4");
+        };
+        let x = x.trim().parse().context("invalid column")?;
+        let y = y.trim().parse().context("invalid row")?;
+        Ok(Self::At(x, y))
+    }
+}
+
+/// ASCII art drawn beneath the rain, like a house or a skyline, that drops collide with
+///
+/// Every character other than a space is solid.
+#[derive(Debug, Clone)]
+pub struct Scene {
+    rows: Vec<Vec<char>>,
+    width: usize,
+    position: ScenePosition,
+}
+impl Scene {
+    pub fn load(path: &Path, position: ScenePosition) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read scene {}", path.display()))?;
+        Self::parse(&text, position).with_context(|| format!("invalid scene {}", path.display()))
+    }
+    pub fn parse(text: &str, position: ScenePosition) -> Result<Self> {
+        let rows = text
+            .lines()
+            .map(|line| line.trim_end().chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        // every character has to fill exactly one cell for collisions to line up with the art
+        if let Some(c) = rows.iter().flatten().find(|c| c.width() != Some(1)) {
+            bail!("scenes can only contain characters one cell wide, found {c:?}");
+        }
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if width == 0 {
+            bail!("scene is empty");
+        }
+        Ok(Self {
+            rows,
+            width,
+            position,
+        })
+    }
+    /// Screen position of the scene's top left corner on a screen of the given size
+    fn origin(&self, width: usize, height: usize) -> (i64, i64) {
+        match self.position {
+            ScenePosition::Bottom => (
+                (width as i64 - self.width as i64) / 2,
+                height as i64 - self.rows.len() as i64,
+            ),
+            ScenePosition::At(x, y) => (x as i64, y as i64),
+        }
+    }
+    /// Whether a cell on a screen of the given size is taken by a solid part of the scene
+    pub fn solid(&self, x: i64, y: i64, width: usize, height: usize) -> bool {
+        let (ox, oy) = self.origin(width, height);
+        let (Ok(x), Ok(y)) = (usize::try_from(x - ox), usize::try_from(y - oy)) else {
+            return false;
+        };
+        self.rows
+            .get(y)
+            .and_then(|row| row.get(x))
+            .is_some_and(|&c| c != ' ')
+    }
+    pub fn render(&self, frame: &mut Frame, fg: Option<Color>, width: usize, height: usize) {
+        let (ox, oy) = self.origin(width, height);
+        for (y, row) in self.rows.iter().enumerate() {
+            for (x, &c) in row.iter().enumerate() {
+                let (x, y) = (ox + x as i64, oy + y as i64);
+                if c != ' ' && x >= 0 && y >= 0 {
+                    frame.set(x as usize, y as usize, Cell::new(c, fg));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use crate::{
+    color::ColorMode,
+    config::Config,
+    input::Action,
+    physics::Physics,
+    rain::{Mode, Weather},
+    scene::Scene,
+    theme::Theme,
+};
+
+/// Simulation settings that can be changed while running, starting out from the config
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub color_mode: ColorMode,
+    pub spawn_rate: u8,
+    pub update_rate: u64,
+    pub mode: Mode,
+    pub weather: Weather,
+    pub wind: f32,
+    pub gustiness: f32,
+    pub physics: Physics,
+    pub storm: bool,
+    pub lightning_frequency: f64,
+    pub lightning_intensity: u8,
+    /// `None` draws drops as streaks following their slope
+    pub glyphs: Option<Vec<char>>,
+    pub snow_glyphs: Vec<char>,
+    pub scene: Option<Scene>,
+    pub themes: Vec<Theme>,
+    /// Index of the current theme in `themes`
+    pub theme: usize,
+    pub paused: bool,
+    pub slow_motion: bool,
+}
+impl From<&Config> for Settings {
+    fn from(config: &Config) -> Self {
+        Self {
+            color_mode: config.color_mode,
+            spawn_rate: config.spawn_rate,
+            update_rate: config.update_rate,
+            mode: config.mode,
+            weather: config.weather,
+            wind: config.wind,
+            gustiness: config.gustiness,
+            physics: config.physics,
+            storm: config.storm,
+            lightning_frequency: config.lightning_frequency,
+            lightning_intensity: config.lightning_intensity,
+            glyphs: config.glyphs.clone(),
+            snow_glyphs: config.snow_glyphs.clone(),
+            scene: config.scene.clone(),
+            themes: config.themes.clone(),
+            theme: config.theme,
+            paused: false,
+            slow_motion: false,
+        }
+    }
+}
+impl Settings {
+    /// Wind change per key press, in cells per update
+    const WIND_STEP: f32 = 0.25;
+    /// Factor the update rate changes by per key press
+    const SPEED_STEP: f64 = 1.25;
+
+    pub fn theme(&self) -> &Theme {
+        &self.themes[self.theme]
+    }
+    /// Applies an action that changes a setting, returning a description of the new value
+    pub fn apply(&mut self, action: Action) -> Option<String> {
+        match action {
+            Action::MoreRain | Action::LessRain => {
+                // fine steps where a single percent is noticeable
+                let step = if self.spawn_rate < 10 { 1 } else { 5 };
+                self.spawn_rate = if action == Action::MoreRain {
+                    self.spawn_rate.saturating_add(step).min(100)
+                } else {
+                    self.spawn_rate.saturating_sub(step).max(1)
+                };
+                Some(format!("spawn rate: {}%", self.spawn_rate))
+            }
+            Action::Faster | Action::Slower => {
+                // at least a millisecond per press, or rounding would keep small rates stuck
+                let rate = if action == Action::Faster {
+                    let rate = (self.update_rate as f64 / Self::SPEED_STEP).round() as u64;
+                    rate.min(self.update_rate.saturating_sub(1))
+                } else {
+                    let rate = (self.update_rate as f64 * Self::SPEED_STEP).round() as u64;
+                    rate.max(self.update_rate + 1)
+                };
+                self.update_rate = rate.clamp(1, 2000);
+                Some(format!("update rate: {}ms", self.update_rate))
+            }
+            Action::WindLeft | Action::WindRight => {
+                let step = if action == Action::WindLeft {
+                    -Self::WIND_STEP
+                } else {
+                    Self::WIND_STEP
+                };
+                self.wind += step;
+                Some(format!("wind: {:+.2}", self.wind))
+            }
+            Action::CycleColor => {
+                self.color_mode = self.color_mode.next();
+                Some(format!("colors: {}", self.color_mode.name()))
+            }
+            Action::CycleTheme => {
+                self.theme = (self.theme + 1) % self.themes.len();
+                Some(format!("theme: {}", self.theme().name))
+            }
+            Action::ToggleWeather => {
+                self.weather = match self.weather {
+                    Weather::Rain => Weather::Snow,
+                    Weather::Snow => Weather::Rain,
+                };
+                let name = if self.weather == Weather::Snow {
+                    "snow"
+                } else {
+                    "rain"
+                };
+                Some(format!("weather: {name}"))
+            }
+            Action::TogglePause => {
+                self.paused = !self.paused;
+                Some(if self.paused { "paused" } else { "resumed" }.to_string())
+            }
+            Action::ToggleSlowMotion => {
+                self.slow_motion = !self.slow_motion;
+                let state = if self.slow_motion { "on" } else { "off" };
+                Some(format!("slow motion: {state}"))
+            }
+            Action::Quit | Action::Suspend | Action::Step => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{config::FileConfig, Opts},
+        clap::Parser,
+    };
+
+    #[test]
+    fn every_speed_change_moves_the_update_rate() {
+        let opts = Opts::parse_from(["cli-rain", "-u", "1"]);
+        let mut settings = Settings::from(&Config::resolve(&opts, FileConfig::default()).unwrap());
+        let mut rates = vec![settings.update_rate];
+        while settings.update_rate < 2000 {
+            settings.apply(Action::Slower);
+            rates.push(settings.update_rate);
+        }
+        assert!(rates.windows(2).all(|w| w[1] > w[0]));
+        assert_eq!(rates[..4], [1, 2, 3, 4]);
+        while settings.update_rate > 1 {
+            let before = settings.update_rate;
+            settings.apply(Action::Faster);
+            assert!(settings.update_rate < before);
+        }
+        // and stays within bounds
+        settings.apply(Action::Faster);
+        assert_eq!(settings.update_rate, 1);
+    }
+}
@@ -0,0 +1,74 @@
+use {
+    anyhow::{Context as _, Result},
+    std::{
+        process::ExitCode,
+        sync::mpsc::{channel, Receiver},
+    },
+};
+
+/// Process signals the main loop has to react to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    /// Exit with the given signal number
+    Exit(i32),
+    /// Stop the process until it is resumed (ctrl-z)
+    Suspend,
+    /// The process was continued after being stopped
+    Resume,
+}
+
+/// Conventional exit status for a process ended by the given signal
+pub fn exit_code(sig: i32) -> ExitCode {
+    ExitCode::from((128 + sig).clamp(0, u8::MAX as i32) as u8)
+}
+
+/// Listens for signals on a background thread and forwards them to the returned channel
+#[cfg(unix)]
+pub fn listen() -> Result<Receiver<Signal>> {
+    use signal_hook::{consts::*, iterator::Signals};
+
+    let mut signals = Signals::new([SIGINT, SIGTERM, SIGHUP, SIGQUIT, SIGTSTP, SIGCONT])
+        .context("failed to register signal handlers")?;
+    let (tx, rx) = channel();
+    std::thread::Builder::new()
+        .name("signals".into())
+        .spawn(move || {
+            for sig in signals.forever() {
+                let signal = match sig {
+                    SIGTSTP => Signal::Suspend,
+                    SIGCONT => Signal::Resume,
+                    sig => Signal::Exit(sig),
+                };
+                if tx.send(signal).is_err() {
+                    break;
+                }
+            }
+        })
+        .context("failed to spawn signal thread")?;
+    Ok(rx)
+}
+
+/// Listens for ctrl-c and forwards it to the returned channel
+#[cfg(not(unix))]
+pub fn listen() -> Result<Receiver<Signal>> {
+    const SIGINT: i32 = 2;
+
+    let (tx, rx) = channel();
+    ctrlc::set_handler(move || {
+        let _ = tx.send(Signal::Exit(SIGINT));
+    })
+    .context("failed to set ctrl-c handler")?;
+    Ok(rx)
+}
+
+/// Stops the process like the default ctrl-z handler would, returns once it is continued
+#[cfg(unix)]
+pub fn suspend() -> Result<()> {
+    signal_hook::low_level::emulate_default_handler(signal_hook::consts::SIGTSTP)
+        .context("failed to suspend")
+}
+
+#[cfg(not(unix))]
+pub fn suspend() -> Result<()> {
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use {
+    crate::{columns::Columns, render::Frame},
+    crossterm::style::Color,
+};
+
+/// Snow that has settled on the bottom of the map, stored as a height (in cells) per column
+pub struct SnowPack {
+    heights: Columns,
+    /// Ticks since the last flake landed
+    ticks_since_snowfall: u32,
+}
+impl SnowPack {
+    /// How much snow a single flake adds to its column
+    const FLAKE_VOLUME: f32 = 0.08;
+    /// Fraction of the map height the snow may fill
+    const MAX_FILL: f32 = 0.5;
+    /// Ticks without a landing flake before the snow starts melting
+    const MELT_DELAY: u32 = 100;
+    /// How much every column melts per tick once it stopped snowing
+    const MELT_RATE: f32 = 0.01;
+    /// Height difference between neighbouring columns before snow slides over
+    const SLIDE_THRESHOLD: f32 = 1.5;
+    /// Share of that difference sliding over per tick
+    const SLIDE_SHARE: f32 = 0.25;
+
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            heights: Columns::new(width, height as f32 * Self::MAX_FILL),
+            ticks_since_snowfall: Self::MELT_DELAY,
+        }
+    }
+    /// Height of the snow in the given column, 0 outside of the map
+    pub fn height(&self, x: usize) -> f32 {
+        self.heights.height(x)
+    }
+    /// Settles a flake onto the given column
+    pub fn land(&mut self, x: usize) {
+        if self.heights.add(x, Self::FLAKE_VOLUME) {
+            self.ticks_since_snowfall = 0;
+        }
+    }
+    /// Lets steep piles slide onto their neighbours and melts the snow once it stopped falling
+    pub fn update(&mut self) {
+        self.heights.level(Self::SLIDE_SHARE, Self::SLIDE_THRESHOLD);
+
+        self.ticks_since_snowfall = self.ticks_since_snowfall.saturating_add(1);
+        if self.ticks_since_snowfall > Self::MELT_DELAY {
+            self.heights.lower(Self::MELT_RATE);
+        }
+    }
+    /// Rescales the columns to the new width and trims the snow to fit the new height
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.heights.resize(width, height as f32 * Self::MAX_FILL);
+    }
+    pub fn render(&self, frame: &mut Frame, fg: Option<Color>, map_height: usize) {
+        self.heights.render(frame, fg, map_height);
+    }
+}
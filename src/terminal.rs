@@ -0,0 +1,51 @@
+use {
+    anyhow::{Context as _, Result},
+    crossterm::{
+        cursor::{Hide, Show},
+        execute,
+        style::ResetColor,
+        terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    },
+    std::{io::stdout, sync::Once},
+};
+
+/// Puts the terminal into the state the rain is drawn in and restores it when dropped,
+/// including when unwinding from a panic
+pub struct TerminalGuard(());
+impl TerminalGuard {
+    pub fn enter() -> Result<Self> {
+        install_panic_hook();
+        setup()?;
+        Ok(Self(()))
+    }
+}
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+/// Switches to the alternate screen in raw mode with the cursor hidden
+pub fn setup() -> Result<()> {
+    enable_raw_mode().context("failed to enable raw mode")?;
+    execute!(stdout(), EnterAlternateScreen, Hide).context("failed to enter alternate screen")
+}
+
+/// Returns the terminal to how it was before [`setup`], safe to call more than once
+pub fn restore() {
+    // nothing sensible left to do if this fails, we are on the way out
+    let _ = execute!(stdout(), ResetColor, Show, LeaveAlternateScreen);
+    let _ = disable_raw_mode();
+}
+
+/// Restores the terminal before the panic message is printed so it ends up on the main screen
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore();
+            default_hook(info);
+        }));
+    });
+}
@@ -0,0 +1,215 @@
+use {
+    crate::{color::brighten, rain::EntityKind},
+    anyhow::{bail, Context as _, Result},
+    crossterm::style::Color,
+    serde::Deserialize,
+    std::collections::BTreeMap,
+};
+
+/// Colors everything is drawn with
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub name: String,
+    drops: Gradient,
+    splashes: Gradient,
+    flakes: Gradient,
+    /// Settled snow
+    snow: Color,
+    /// Scene the rain falls on
+    scene: Color,
+    /// Text the rain falls over, before it wears away
+    text: Color,
+    background: Option<Color>,
+}
+impl Theme {
+    /// How far the head of a matrix stream is moved from the nearest drop color towards white
+    const STREAM_HEAD_LIFT: f32 = 0.6;
+
+    /// Themes that are always available, the first one is the default
+    pub fn built_in() -> Vec<Self> {
+        let theme = |name: &str, drops: &[(u8, u8, u8)], splashes: &[(u8, u8, u8)]| Self {
+            name: name.to_string(),
+            drops: Gradient::even(drops),
+            splashes: Gradient::even(splashes),
+            flakes: Gradient::even(&[(96, 96, 112), (255, 255, 255)]),
+            snow: rgb((225, 230, 240)),
+            scene: rgb((120, 120, 130)),
+            text: rgb((200, 200, 200)),
+            background: None,
+        };
+        vec![
+            theme(
+                "rain",
+                &[(0, 0, 0), (0, 127, 255)],
+                &[(0, 0, 0), (0, 127, 255)],
+            ),
+            theme(
+                "matrix",
+                &[(0, 20, 0), (0, 140, 40), (150, 255, 150)],
+                &[(0, 60, 0), (0, 255, 70)],
+            ),
+            theme(
+                "acid-rain",
+                &[(40, 0, 60), (120, 200, 0), (220, 255, 40)],
+                &[(80, 120, 0), (240, 255, 80)],
+            ),
+            theme(
+                "sunset",
+                &[(40, 0, 60), (200, 40, 90), (255, 150, 40), (255, 230, 120)],
+                &[(200, 40, 90), (255, 200, 80)],
+            ),
+            theme(
+                "monochrome",
+                &[(40, 40, 40), (255, 255, 255)],
+                &[(100, 100, 100), (220, 220, 220)],
+            ),
+        ]
+    }
+    /// Color of an entity of the given kind at depth `t`, 0 is farthest and 1 is closest
+    pub fn entity(&self, kind: &EntityKind, t: f32) -> Color {
+        match kind {
+            EntityKind::Drop { .. } | EntityKind::Runoff { .. } => self.drops.at(t),
+            EntityKind::Splash => self.splashes.at(t),
+            EntityKind::Flake { .. } => self.flakes.at(t),
+        }
+    }
+    /// Color of a matrix stream glyph `t` of the way from the end of its trail (0) to its head (1)
+    pub fn stream(&self, t: f32) -> Color {
+        self.drops.at(t)
+    }
+    /// Color of the leading glyph of a matrix stream, lighter than anything in the trail
+    pub fn stream_head(&self) -> Color {
+        brighten(self.drops.at(1.0), Self::STREAM_HEAD_LIFT)
+    }
+    /// Water pooling on the ground
+    pub fn puddle(&self) -> Color {
+        self.drops.at(0.5)
+    }
+    pub fn snow(&self) -> Color {
+        self.snow
+    }
+    pub fn scene(&self) -> Color {
+        self.scene
+    }
+    /// Color of text being rained over, fading into the background as it wears away (0-1)
+    pub fn text(&self, wear: f32) -> Color {
+        let channels = |color| match color {
+            Color::Rgb { r, g, b } => (r, g, b),
+            _ => unreachable!("theme colors are always rgb"),
+        };
+        let background = self.background.map_or((0, 0, 0), channels);
+        Gradient::even(&[channels(self.text), background]).at(wear)
+    }
+    pub fn background(&self) -> Option<Color> {
+        self.background
+    }
+}
+
+/// A theme as written in the config file, colors are `#rrggbb` and gradients go from far to near
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ThemeConfig {
+    drops: Vec<String>,
+    /// Defaults to the drop colors
+    splashes: Option<Vec<String>>,
+    flakes: Option<Vec<String>>,
+    snow: Option<String>,
+    scene: Option<String>,
+    text: Option<String>,
+    background: Option<String>,
+}
+
+/// The built-in themes followed by the user's, a user theme with a built-in name replaces it
+pub fn load_themes(user: &BTreeMap<String, ThemeConfig>) -> Result<Vec<Theme>> {
+    let mut themes = Theme::built_in();
+    let default = themes[0].clone();
+    for (name, config) in user {
+        let gradient = |colors: &[String]| -> Result<Gradient> {
+            let colors = colors
+                .iter()
+                .map(|c| parse_hex(c))
+                .collect::<Result<Vec<_>>>()?;
+            if colors.is_empty() {
+                bail!("gradients need at least one color");
+            }
+            Ok(Gradient::even(&colors))
+        };
+        let theme = (|| -> Result<Theme> {
+            let drops = gradient(&config.drops)?;
+            Ok(Theme {
+                name: name.clone(),
+                splashes: match &config.splashes {
+                    Some(colors) => gradient(colors)?,
+                    None => drops.clone(),
+                },
+                drops,
+                flakes: match &config.flakes {
+                    Some(colors) => gradient(colors)?,
+                    None => default.flakes.clone(),
+                },
+                snow: match &config.snow {
+                    Some(color) => rgb(parse_hex(color)?),
+                    None => default.snow,
+                },
+                scene: match &config.scene {
+                    Some(color) => rgb(parse_hex(color)?),
+                    None => default.scene,
+                },
+                text: match &config.text {
+                    Some(color) => rgb(parse_hex(color)?),
+                    None => default.text,
+                },
+                background: config
+                    .background
+                    .as_deref()
+                    .map(|c| parse_hex(c).map(rgb))
+                    .transpose()?,
+            })
+        })()
+        .with_context(|| format!("invalid theme {name:?}"))?;
+
+        match themes.iter_mut().find(|t| t.name == theme.name) {
+            Some(existing) => *existing = theme,
+            None => themes.push(theme),
+        }
+    }
+    Ok(themes)
+}
+
+/// Colors spread along 0-1, interpolated in between
+#[derive(Debug, Clone)]
+struct Gradient {
+    stops: Vec<(u8, u8, u8)>,
+}
+impl Gradient {
+    /// A gradient with the colors evenly spaced
+    fn even(colors: &[(u8, u8, u8)]) -> Self {
+        Self {
+            stops: colors.to_vec(),
+        }
+    }
+    fn at(&self, t: f32) -> Color {
+        let last = self.stops.len() - 1;
+        let pos = t.clamp(0.0, 1.0) * last as f32;
+        let i = (pos as usize).min(last.saturating_sub(1));
+        let (a, b) = (self.stops[i], self.stops[(i + 1).min(last)]);
+        let f = pos - i as f32;
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * f).round() as u8;
+        rgb((mix(a.0, b.0), mix(a.1, b.1), mix(a.2, b.2)))
+    }
+}
+
+fn rgb((r, g, b): (u8, u8, u8)) -> Color {
+    Color::Rgb { r, g, b }
+}
+
+fn parse_hex(s: &str) -> Result<(u8, u8, u8)> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if hex.len() != 6 || !hex.is_ascii() {
+        bail!("expected a color like #00aaff, got {s:?}");
+    }
+    let channel = |i: usize| {
+        u8::from_str_radix(&hex[i..i + 2], 16).with_context(|| format!("invalid color {s:?}"))
+    };
+    Ok((channel(0)?, channel(2)?, channel(4)?))
+}
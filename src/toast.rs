@@ -0,0 +1,30 @@
+use {
+    crate::render::Frame,
+    std::time::{Duration, Instant},
+    unicode_width::UnicodeWidthStr,
+};
+
+/// Short message shown at the top of the screen for a moment
+pub struct Toast {
+    text: String,
+    shown_at: Instant,
+}
+impl Toast {
+    const DURATION: Duration = Duration::from_millis(1500);
+
+    pub fn new(text: String) -> Self {
+        Self {
+            text: format!(" {text} "),
+            shown_at: Instant::now(),
+        }
+    }
+    pub fn expired(&self) -> bool {
+        self.shown_at.elapsed() >= Self::DURATION
+    }
+    /// Draws the message centered on the top row
+    pub fn render(&self, frame: &mut Frame) {
+        let len = self.text.width();
+        let x = frame.width().saturating_sub(len) / 2;
+        frame.print(x, 0, &self.text, None);
+    }
+}
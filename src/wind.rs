@@ -0,0 +1,74 @@
+/// Wind blowing across the whole map, a steady base speed plus gusts that change smoothly over
+/// time and height
+pub struct Wind {
+    /// Steady horizontal speed, in cells per tick (positive blows to the right)
+    base: f32,
+    /// Largest extra speed gusts add on top of the base, in cells per tick
+    gustiness: f32,
+    seed: u64,
+    tick: u64,
+}
+impl Wind {
+    /// How quickly gusts change over time (noise cells per tick)
+    const TIME_SCALE: f32 = 0.02;
+    /// How quickly gusts change with height (noise cells per row)
+    const HEIGHT_SCALE: f32 = 0.08;
+
+    pub fn new(seed: u64) -> Self {
+        Self {
+            base: 0.0,
+            gustiness: 0.0,
+            seed,
+            tick: 0,
+        }
+    }
+    /// Advances the gusts by one tick, picking up the current wind settings
+    pub fn update(&mut self, base: f32, gustiness: f32) {
+        self.base = base;
+        self.gustiness = gustiness;
+        self.tick += 1;
+    }
+    /// Horizontal wind speed at the given row
+    pub fn at(&self, y: f32) -> f32 {
+        if self.gustiness == 0.0 {
+            return self.base;
+        }
+        let gust = value_noise(
+            self.seed,
+            self.tick as f32 * Self::TIME_SCALE,
+            y * Self::HEIGHT_SCALE,
+        );
+        self.base + gust * self.gustiness
+    }
+}
+
+/// Smooth 2d value noise in the range -1..=1
+fn value_noise(seed: u64, x: f32, y: f32) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (smoothstep(x - x0), smoothstep(y - y0));
+    let (x0, y0) = (x0 as i64, y0 as i64);
+    let lattice = |x: i64, y: i64| hash(seed, x, y);
+    let top = lerp(lattice(x0, y0), lattice(x0 + 1, y0), tx);
+    let bottom = lerp(lattice(x0, y0 + 1), lattice(x0 + 1, y0 + 1), tx);
+    lerp(top, bottom, ty)
+}
+
+/// Pseudo random value in the range -1..=1 for a lattice point
+fn hash(seed: u64, x: i64, y: i64) -> f32 {
+    // splitmix64 finalizer over the combined coordinates
+    let mut h = seed
+        ^ (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    h = (h ^ (h >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    h ^= h >> 31;
+    (h >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+}
+
+fn smoothstep(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
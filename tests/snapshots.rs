@@ -0,0 +1,109 @@
+//! Golden-file tests of headless output, run with `UPDATE_SNAPSHOTS=1` to regenerate the files
+//! after an intended change to the simulation.
+
+use std::{fs, path::Path, process::Command};
+
+fn assert_snapshot(name: &str, args: &[&str]) {
+    let output = Command::new(env!("CARGO_BIN_EXE_cli-rain"))
+        .args(["--headless", "--seed", "1", "--size", "60x20"])
+        .args(args)
+        // keep the user's config file and CLI_RAIN_* variables out of the snapshots
+        .env_clear()
+        .output()
+        .expect("failed to run cli-rain");
+    assert!(
+        output.status.success(),
+        "cli-rain failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let actual = String::from_utf8(output.stdout).expect("frame is not utf-8");
+
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(format!("{name}.txt"));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, &actual).expect("failed to write snapshot");
+        return;
+    }
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("failed to read {}: {e}", path.display()));
+    assert_eq!(
+        actual,
+        expected,
+        "frame differs from {}, rerun with UPDATE_SNAPSHOTS=1 if the change is intended",
+        path.display()
+    );
+}
+
+#[test]
+fn rain() {
+    assert_snapshot("rain", &["--frames", "60", "-r", "10"]);
+}
+
+#[test]
+fn windy_rain() {
+    assert_snapshot(
+        "windy_rain",
+        &[
+            "--frames",
+            "60",
+            "-r",
+            "10",
+            "--wind",
+            "-1.5",
+            "--gustiness",
+            "1",
+        ],
+    );
+}
+
+#[test]
+fn snow() {
+    assert_snapshot("snow", &["--frames", "200", "-r", "20", "-w", "snow"]);
+}
+
+#[test]
+fn wide_glyphs() {
+    assert_snapshot(
+        "wide_glyphs",
+        &["--frames", "60", "-r", "10", "--glyphs", "katakana"],
+    );
+}
+
+#[test]
+fn matrix() {
+    assert_snapshot(
+        "matrix",
+        &["--frames", "60", "-r", "10", "--mode", "matrix"],
+    );
+}
+
+#[test]
+fn scene() {
+    assert_snapshot(
+        "scene",
+        &[
+            "--frames",
+            "60",
+            "-r",
+            "30",
+            "--scene",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/scenes/house.txt"),
+        ],
+    );
+}
+
+#[test]
+fn over_text() {
+    assert_snapshot(
+        "over_text",
+        &[
+            "--frames",
+            "60",
+            "-r",
+            "10",
+            "--over",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/text/listing.txt"),
+        ],
+    );
+}